@@ -0,0 +1,114 @@
+use std::hint::black_box;
+
+use av1_obu_parser::buffer::Buffer;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const SIZE: usize = 64 * 1024;
+const OBUS: usize = 16 * 1024;
+
+fn bench_get_bits(c: &mut Criterion) {
+    let bytes = (0..SIZE).map(|i| (i * 31 + 7) as u8).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("get_bits");
+    group.throughput(Throughput::Bytes(SIZE as u64));
+    for count in [1, 4, 8, 12, 16, 32] {
+        let reads = SIZE * 8 / count;
+
+        group.bench_with_input(
+            BenchmarkId::new("window", count),
+            &count,
+            |b, &count| {
+                b.iter(|| {
+                    let mut buf = Buffer::new(black_box(&bytes));
+                    for _ in 0..reads {
+                        black_box(buf.get_bits(count));
+                    }
+                })
+            },
+        );
+
+        // The bit by bit reading `get_bits` used to do, as the baseline.
+        group.bench_with_input(
+            BenchmarkId::new("bit_by_bit", count),
+            &count,
+            |b, &count| {
+                b.iter(|| {
+                    let mut buf = Buffer::new(black_box(&bytes));
+                    for _ in 0..reads {
+                        let mut value = 0u32;
+                        for i in 0..count {
+                            value |= (buf.get_bit() as u32) << (count - i - 1);
+                        }
+
+                        black_box(value);
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// The byte by byte reading `get_leb128` used to do, as the baseline.
+fn get_leb128_scalar(buf: &mut Buffer) -> Option<u32> {
+    let mut value = 0u64;
+    for i in 0..8 {
+        let byte = buf.get_bits(8) as u64;
+        value |= (byte & 0x7f) << (i * 7);
+        if byte & 0x80 == 0 {
+            return u32::try_from(value).ok();
+        }
+    }
+
+    None
+}
+
+/// Walks the OBUs of `bytes` using only their headers and `obu_size`.
+fn frame_obus(bytes: &[u8], get_leb128: impl Fn(&mut Buffer) -> Option<u32>) -> usize {
+    let mut buf = Buffer::new(bytes);
+
+    let mut count = 0;
+    while buf.remaining_bytes() > 0 {
+        buf.seek_bits(8);
+
+        let size = get_leb128(&mut buf).unwrap() as usize;
+        buf.seek_bits(size * 8);
+        count += 1;
+    }
+
+    count
+}
+
+fn bench_leb128(c: &mut Criterion) {
+    // Padding OBUs of 0 to 299 bytes, with one and two byte obu_size.
+    let mut bytes = Vec::new();
+    for i in 0..OBUS {
+        let size = i % 300;
+
+        bytes.push(0b0111_1010);
+        if size < 128 {
+            bytes.push(size as u8);
+        } else {
+            bytes.extend([(size as u8) | 0x80, (size >> 7) as u8]);
+        }
+
+        bytes.extend(std::iter::repeat_n(0, size));
+    }
+
+    let mut group = c.benchmark_group("leb128_framing");
+    group.throughput(Throughput::Elements(OBUS as u64));
+    group.bench_function("optimized", |b| {
+        b.iter(|| frame_obus(black_box(&bytes), |buf| buf.get_leb128()))
+    });
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| frame_obus(black_box(&bytes), get_leb128_scalar))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_bits, bench_leb128);
+criterion_main!(benches);
@@ -0,0 +1,234 @@
+use std::hint::black_box;
+
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{ObuHeader, ObuParser, ObuType, metadata::Metadata},
+    writer::Writer,
+};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const TEMPORAL_UNITS: usize = 64;
+const TILE_SIZE: usize = 4096;
+
+fn obu(writer: &mut Writer, r#type: ObuType, payload: &[u8]) {
+    ObuHeader {
+        r#type,
+        has_size: true,
+        extension: None,
+    }
+    .encode(writer);
+
+    writer.put_leb128(payload.len() as u32);
+    writer.put_bytes(payload);
+}
+
+fn byte_alignment(writer: &mut Writer) {
+    while !writer.get_position().is_multiple_of(8) {
+        writer.put_bit(false);
+    }
+}
+
+/// Main profile, 1920x1080, 8 bit 4:2:0 with order hints, level 4.0.
+fn sequence_header() -> Vec<u8> {
+    let mut writer = Writer::new();
+
+    // seq_profile, still_picture, reduced_still_picture_header
+    writer.put_bits(3, 0);
+    writer.put_bits(2, 0);
+
+    // timing_info_present_flag, initial_display_delay_present_flag
+    writer.put_bits(2, 0);
+
+    // operating_points_cnt_minus_1, operating_point_idc[0], seq_level_idx[0], seq_tier[0]
+    writer.put_bits(5, 0);
+    writer.put_bits(12, 0);
+    writer.put_bits(5, 8);
+    writer.put_bit(false);
+
+    // frame_width_bits_minus_1, frame_height_bits_minus_1
+    writer.put_bits(4, 10);
+    writer.put_bits(4, 10);
+
+    // max_frame_width_minus_1, max_frame_height_minus_1
+    writer.put_bits(11, 1919);
+    writer.put_bits(11, 1079);
+
+    // frame_id_numbers_present_flag .. enable_dual_filter
+    writer.put_bits(8, 0);
+
+    // enable_order_hint, enable_jnt_comp, enable_ref_frame_mvs
+    writer.put_bits(3, 0b100);
+
+    // seq_choose_screen_content_tools, seq_choose_integer_mv
+    writer.put_bits(2, 0b11);
+
+    // order_hint_bits_minus_1
+    writer.put_bits(3, 6);
+
+    // enable_superres, enable_cdef, enable_restoration
+    writer.put_bits(3, 0b011);
+
+    // high_bitdepth, mono_chrome, color_description_present_flag, color_range,
+    // chroma_sample_position, separate_uv_delta_q
+    writer.put_bits(7, 0);
+
+    // film_grain_params_present
+    writer.put_bit(false);
+
+    writer.trailing_bits();
+    writer.into_bytes()
+}
+
+/// A lossless key frame split in 2x2 tiles of `TILE_SIZE` bytes each.
+fn frame() -> Vec<u8> {
+    let mut writer = Writer::new();
+
+    // show_existing_frame, frame_type, show_frame
+    writer.put_bit(false);
+    writer.put_bits(2, 0);
+    writer.put_bit(true);
+
+    // disable_cdf_update, allow_screen_content_tools, frame_size_override_flag
+    writer.put_bits(3, 0);
+
+    // order_hint
+    writer.put_bits(7, 0);
+
+    // render_and_frame_size_different, disable_frame_end_update_cdf
+    writer.put_bits(2, 0);
+
+    // uniform_tile_spacing_flag, increment_tile_cols_log2 x2, increment_tile_rows_log2 x2
+    writer.put_bits(5, 0b11010);
+
+    // context_update_tile_id, tile_size_bytes_minus_1
+    writer.put_bits(2, 0);
+    writer.put_bits(2, 1);
+
+    // base_q_idx, delta_coded for DeltaQYDc, DeltaQUDc and DeltaQUAc, using_qmatrix
+    writer.put_bits(8, 0);
+    writer.put_bits(4, 0);
+
+    // segmentation_enabled_flag, reduced_tx_set
+    writer.put_bits(2, 0);
+    byte_alignment(&mut writer);
+
+    // tile_start_and_end_present_flag
+    writer.put_bit(false);
+    byte_alignment(&mut writer);
+
+    for tile in 0..4 {
+        if tile < 3 {
+            // tile_size_minus_1	le(TileSizeBytes)
+            writer.put_bits(8, (TILE_SIZE - 1) as u32 & 0xff);
+            writer.put_bits(8, (TILE_SIZE - 1) as u32 >> 8);
+        }
+
+        writer.put_bytes(&[tile as u8; TILE_SIZE]);
+    }
+
+    writer.into_bytes()
+}
+
+fn metadata() -> Vec<u8> {
+    let mut writer = Writer::new();
+    Metadata::HdrCll {
+        max_cll: 1000,
+        max_fall: 400,
+    }
+    .encode(&mut writer);
+
+    writer.trailing_bits();
+    writer.into_bytes()
+}
+
+/// A stream made of the sequence header followed by `TEMPORAL_UNITS`
+/// temporal units, each a temporal delimiter, a metadata OBU and a frame.
+fn stream() -> Vec<u8> {
+    let mut writer = Writer::new();
+    obu(&mut writer, ObuType::TemporalDelimiter, &[]);
+    obu(&mut writer, ObuType::SequenceHeader, &sequence_header());
+
+    let frame = frame();
+    let metadata = metadata();
+    for i in 0..TEMPORAL_UNITS {
+        if i > 0 {
+            obu(&mut writer, ObuType::TemporalDelimiter, &[]);
+        }
+
+        obu(&mut writer, ObuType::Metadata, &metadata);
+        obu(&mut writer, ObuType::Frame, &frame);
+    }
+
+    writer.into_bytes()
+}
+
+/// A stream repeating a single OBU type, preceded by a sequence header when
+/// the OBU needs one.
+fn stream_of(r#type: ObuType, count: usize) -> Vec<u8> {
+    let payload = match r#type {
+        ObuType::SequenceHeader => sequence_header(),
+        ObuType::Metadata => metadata(),
+        ObuType::Frame => frame(),
+        _ => Vec::new(),
+    };
+
+    let mut writer = Writer::new();
+    if r#type == ObuType::Frame {
+        obu(&mut writer, ObuType::SequenceHeader, &sequence_header());
+    }
+
+    for _ in 0..count {
+        if r#type == ObuType::Frame {
+            obu(&mut writer, ObuType::TemporalDelimiter, &[]);
+        }
+
+        obu(&mut writer, r#type, &payload);
+    }
+
+    writer.into_bytes()
+}
+
+fn parse_all(bytes: &[u8]) -> usize {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(bytes);
+
+    let mut count = 0;
+    for obu in parser.iter(&mut buf) {
+        black_box(obu.unwrap());
+        count += 1;
+    }
+
+    count
+}
+
+fn bench_stream(c: &mut Criterion) {
+    let bytes = stream();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("stream", |b| b.iter(|| parse_all(black_box(&bytes))));
+    group.finish();
+}
+
+fn bench_obu_type(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_obu_type");
+    for (name, r#type, count) in [
+        ("temporal_delimiter", ObuType::TemporalDelimiter, 4096),
+        ("sequence_header", ObuType::SequenceHeader, 1024),
+        ("metadata", ObuType::Metadata, 1024),
+        ("frame", ObuType::Frame, TEMPORAL_UNITS),
+    ] {
+        let bytes = stream_of(r#type, count);
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &bytes, |b, bytes| {
+            b.iter(|| parse_all(black_box(bytes)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_stream, bench_obu_type);
+criterion_main!(benches);
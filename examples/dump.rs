@@ -0,0 +1,42 @@
+use tokio::{fs::OpenOptions, io::AsyncReadExt};
+
+use clap::Parser;
+use av1_obu_parser::{buffer::Buffer, obu::ObuParser};
+
+/// Prints every OBU of a low overhead bitstream as a line of JSON.
+#[derive(Parser)]
+#[command(
+    about = env!("CARGO_PKG_DESCRIPTION"),
+    version = env!("CARGO_PKG_VERSION"),
+    author = env!("CARGO_PKG_AUTHORS"),
+)]
+struct Configure {
+    #[arg(long)]
+    input: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let config = Configure::parse();
+    let mut parser = ObuParser::default();
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .open(&config.input)
+        .await
+        .unwrap();
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.unwrap();
+
+    let mut buffer = Buffer::new(&buf);
+    for obu in parser.iter(&mut buffer) {
+        match obu {
+            Ok(obu) => println!("{}", serde_json::to_string(&obu).unwrap()),
+            Err(e) => {
+                eprintln!("{:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
+use std::io::{self, Read};
+
+use crate::math::floor_log2;
+
+#[derive(Clone)]
 pub struct Buffer<'a> {
     buf: &'a [u8],
     index: usize,
     bit_pos: usize,
+    #[cfg(feature = "checked-buffer")]
+    out_of_bounds: bool,
 }
 
 impl<'a> Buffer<'a> {
@@ -10,37 +17,159 @@ impl<'a> Buffer<'a> {
             buf,
             index: 0,
             bit_pos: 0,
+            #[cfg(feature = "checked-buffer")]
+            out_of_bounds: false,
         }
     }
 
+    /// Reads `reader` until the end of the stream, the returned bytes are
+    /// parsed through `OwnedBuffer::as_buffer`.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<OwnedBuffer> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        Ok(OwnedBuffer { bytes })
+    }
+
+    /// Takes ownership of `bytes`, for callers that can't keep the bytes
+    /// alive for the lifetime of a borrowing `Buffer`.
+    pub fn new_owned(bytes: Vec<u8>) -> OwnedBuffer {
+        OwnedBuffer::from(bytes)
+    }
+
     pub fn seek_bits(&mut self, cut: usize) {
-        for _ in 0..cut {
-            self.advance();
+        self.set_position(self.get_position() + cut);
+    }
+
+    /// The current position in the bitstream, in bits.
+    pub fn get_position(&self) -> usize {
+        self.index * 8 + self.bit_pos
+    }
+
+    /// Moves to `position` bits from the start of the bitstream.
+    pub fn set_position(&mut self, position: usize) {
+        let position = position.min(self.buf.len() * 8);
+        self.index = position / 8;
+        self.bit_pos = position % 8;
+    }
+
+    /// The number of whole bytes left in the bitstream.
+    pub fn remaining_bytes(&self) -> usize {
+        self.buf.len() - self.index
+    }
+
+    /// Skips the zero bits up to the next byte boundary.
+    pub fn byte_alignment(&mut self) {
+        if self.bit_pos != 0 {
+            self.seek_bits(8 - self.bit_pos);
         }
     }
 
-    pub fn get_bytes(&mut self, count: usize) -> &[u8] {
+    /// Consumes `count` bits of trailing bits, a stop bit equal to 1 followed
+    /// by zero bits. Returns false if the bits don't match this pattern.
+    pub fn trailing_bits(&mut self, count: usize) -> bool {
+        // trailing_one_bit f(1)
+        let mut valid = self.get_bit();
+        for _ in 1..count {
+            // trailing_zero_bit f(1)
+            valid &= !self.get_bit();
+        }
+
+        valid
+    }
+
+    /// Returns the next `count` bytes without copying them.
+    ///
+    /// Note: The bitstream position must be byte aligned.
+    pub fn read_bytes(&mut self, count: usize) -> &'a [u8] {
         assert_eq!(self.bit_pos, 0);
 
+        #[cfg(feature = "checked-buffer")]
+        if count > self.remaining_bytes() {
+            return self.out_of_bounds();
+        }
+
+        assert!(
+            count <= self.remaining_bytes(),
+            "read past the end of the buffer"
+        );
+
         self.index += count;
         &self.buf[self.index - count..self.index]
     }
 
+    /// Same as `read_bytes`, with the bytes borrowed from the buffer.
+    pub fn get_bytes(&mut self, count: usize) -> &[u8] {
+        self.read_bytes(count)
+    }
+
     pub fn get_bit(&mut self) -> bool {
         self.next()
     }
 
+    /// A one bit flag, same as `get_bit`.
+    #[inline]
+    pub fn get_flag(&mut self) -> bool {
+        self.get_bit()
+    }
+
+    /// `get_bits` for syntax elements of at most 8 bits.
+    #[inline]
+    pub fn get_bits_u8(&mut self, count: usize) -> u8 {
+        debug_assert!(count <= 8);
+
+        self.get_bits(count) as u8
+    }
+
+    /// `get_bits` for syntax elements of at most 16 bits.
+    #[inline]
+    pub fn get_bits_u16(&mut self, count: usize) -> u16 {
+        debug_assert!(count <= 16);
+
+        self.get_bits(count) as u16
+    }
+
     /// Unsigned n-bit number appearing directly in the bitstream. The bits are
     /// read from high to low order.
+    ///
+    /// Note: Some syntax elements have a derived length which can be 0 (e.g.
+    /// order_hint when OrderHintBits is 0), nothing is read and 0 is returned.
+    ///
+    /// Note: The bits are read from a window of the bytes they span rather
+    /// than from a cached accumulator. The position is the only state, so
+    /// clones, `set_position`, `seek_bits` and `read_bytes` don't have to
+    /// refill or flush anything.
     pub fn get_bits(&mut self, count: usize) -> u32 {
-        assert!(count > 0 && count <= 32);
+        assert!(count <= 32);
 
-        let mut aac = 0;
-        for i in 0..count {
-            aac |= (self.get_bit() as u32) << (count - i - 1);
+        match count {
+            0 => return 0,
+            1 => return self.get_bit() as u32,
+            _ => (),
+        }
+
+        let end = self.get_position() + count;
+
+        #[cfg(feature = "checked-buffer")]
+        if end > self.buf.len() * 8 {
+            return self.out_of_bounds();
+        }
+
+        assert!(end <= self.buf.len() * 8, "read past the end of the buffer");
+
+        // The bits span at most 5 bytes, which are loaded at once instead of
+        // reading the bits one by one.
+        let bytes = (self.bit_pos + count).div_ceil(8);
+        let mut acc = 0u64;
+        for byte in &self.buf[self.index..self.index + bytes] {
+            acc = (acc << 8) | *byte as u64;
         }
 
-        aac
+        let shift = bytes * 8 - self.bit_pos - count;
+        self.index = end / 8;
+        self.bit_pos = end % 8;
+
+        (acc >> shift) as u32 & (u32::MAX >> (32 - count))
     }
 
     /// Variable length unsigned n-bit number appearing directly in the
@@ -52,6 +181,12 @@ impl<'a> Buffer<'a> {
                 break;
             }
 
+            // Past the end of the buffer the bits read as zeros.
+            #[cfg(feature = "checked-buffer")]
+            if self.out_of_bounds {
+                break;
+            }
+
             lz += 1;
         }
 
@@ -102,33 +237,231 @@ impl<'a> Buffer<'a> {
     /// It is a requirement of bitstream conformance that the most significant
     /// bit of leb128_byte is equal to 0 if i is equal to 7. (This
     /// ensures that this syntax descriptor never uses more than 8 bytes.)
-    pub fn get_leb128(&mut self) -> u16 {
+    ///
+    /// Returns `None` when either requirement is not met, or when the
+    /// bitstream ends before the last byte of the leb128, in which case
+    /// nothing is consumed.
+    pub fn get_leb128(&mut self) -> Option<u32> {
         assert_eq!(self.bit_pos, 0);
 
-        let mut value = 0;
-        for i in 0..8 {
-            let byte = self.get_bits(8) as u16;
-            value |= (byte & 0x7f) << (i * 7);
-            if byte & 0x80 == 0 {
-                break;
+        // Most obu_size values fit in a single byte.
+        if let Some(&byte) = self.buf.get(self.index)
+            && byte & 0x80 == 0
+        {
+            self.index += 1;
+            return Some(byte as u32);
+        }
+
+        // The 8 bytes a leb128 can span are read as one word, unless the
+        // bitstream is too short for that.
+        if let Some(bytes) = self.buf.get(self.index..self.index + 8) {
+            let word = u64::from_le_bytes(bytes.try_into().unwrap());
+            let stops = !word & 0x8080_8080_8080_8080;
+            if stops == 0 {
+                self.index += 8;
+                return None;
             }
+
+            let len = stops.trailing_zeros() as usize / 8 + 1;
+            self.index += len;
+
+            let word = if len == 8 {
+                word
+            } else {
+                word & ((1 << (len * 8)) - 1)
+            };
+
+            return u32::try_from(leb128_value(word)).ok();
         }
 
-        value
+        self.get_leb128_scalar()
+    }
+
+    fn get_leb128_scalar(&mut self) -> Option<u32> {
+        // Less than 8 bytes are left, the last byte of the leb128 has to be
+        // among them.
+        let len = self.buf[self.index..].iter().position(|byte| byte & 0x80 == 0)? + 1;
+
+        let mut value = 0u64;
+        for i in 0..len {
+            let byte = self.get_bits(8) as u64;
+            value |= (byte & 0x7f) << (i * 7);
+        }
+
+        u32::try_from(value).ok()
     }
 
     /// Signed integer converted from an n bits unsigned integer in the
     /// bitstream. (The unsigned integer corresponds to the bottom n bits of
     /// the signed integer.)
     pub fn get_su(&mut self, count: usize) -> i32 {
-        let mut value = self.get_bits(count) as i32;
-        let sign_mask = 1 << (count - 1) as i32;
+        let mut value = self.get_bits(count) as i64;
+        let sign_mask = 1 << (count - 1);
 
-        if value > 0 && sign_mask > 0 {
-            value = value - 2 * sign_mask;
+        if value & sign_mask != 0 {
+            value -= 2 * sign_mask;
         }
 
-        value
+        value as i32
+    }
+
+    /// Unsigned encoded integer with maximum number of values `n` (i.e. output
+    /// in range 0..n-1).
+    pub fn get_ns(&mut self, n: u32) -> u32 {
+        let w = floor_log2(n) as usize + 1;
+        let m = (1 << w) - n;
+        let v = self.get_bits(w - 1);
+        if v < m {
+            return v;
+        }
+
+        // extra_bit	f(1)
+        let extra_bit = self.get_bit() as u32;
+        (v << 1) - m + extra_bit
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#signed-subexp-with-ref-syntax
+    pub fn get_signed_subexp_with_ref(&mut self, low: i32, high: i32, r: i32) -> i32 {
+        let x = self.get_unsigned_subexp_with_ref((high - low) as u32, (r - low) as u32);
+        x as i32 + low
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#unsigned-subexp-with-ref-syntax
+    pub fn get_unsigned_subexp_with_ref(&mut self, mx: u32, r: u32) -> u32 {
+        let v = self.get_subexp(mx);
+        if (r << 1) <= mx {
+            inverse_recenter(r, v)
+        } else {
+            mx - 1 - inverse_recenter(mx - 1 - r, v)
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#sub-exponential-syntax
+    pub fn get_subexp(&mut self, num_syms: u32) -> u32 {
+        let mut i = 0;
+        let mut mk = 0;
+        let k = 3;
+        loop {
+            let b2 = if i > 0 { k + i - 1 } else { k };
+            let a = 1 << b2;
+            if num_syms <= mk + 3 * a {
+                // subexp_final_bits	ns(numSyms - mk)
+                return self.get_ns(num_syms - mk) + mk;
+            }
+
+            // subexp_more_bits	f(1)
+            if self.get_bit() {
+                i += 1;
+                mk += a;
+            } else {
+                // subexp_bits	f(b2)
+                return self.get_bits(b2 as usize) + mk;
+            }
+        }
+    }
+}
+
+/// Bounds checked reads.
+///
+/// Without the feature the reads above panic past the end of the buffer. With
+/// it, they read zeros (or no bytes) instead, move to the end of the buffer and
+/// flag the buffer, so that a parser can decode the whole OBU and check the
+/// buffer once with `check`. The `try_` variants check the remaining bits
+/// first and return `OutOfBounds` right away, at the cost of a `Result` per
+/// read.
+#[cfg(feature = "checked-buffer")]
+impl<'a> Buffer<'a> {
+    /// Returns `OutOfBounds` if a read went past the end of the buffer.
+    pub fn check(&self) -> Result<(), OutOfBounds> {
+        if self.out_of_bounds {
+            return Err(OutOfBounds);
+        }
+
+        Ok(())
+    }
+
+    /// `get_bit` returning `OutOfBounds` past the end of the buffer.
+    pub fn try_get_bit(&mut self) -> Result<bool, OutOfBounds> {
+        self.check_remaining_bits(1)?;
+
+        Ok(self.get_bit())
+    }
+
+    /// `get_bits` returning `OutOfBounds` past the end of the buffer, the
+    /// position is left unchanged on error.
+    pub fn try_get_bits(&mut self, count: usize) -> Result<u32, OutOfBounds> {
+        self.check_remaining_bits(count)?;
+
+        Ok(self.get_bits(count))
+    }
+
+    /// `read_bytes` returning `OutOfBounds` past the end of the buffer.
+    ///
+    /// Note: The bitstream position must be byte aligned.
+    pub fn try_read_bytes(&mut self, count: usize) -> Result<&'a [u8], OutOfBounds> {
+        self.check_remaining_bits(count * 8)?;
+
+        Ok(self.read_bytes(count))
+    }
+
+    fn out_of_bounds<T: Default>(&mut self) -> T {
+        self.out_of_bounds = true;
+        self.set_position(self.buf.len() * 8);
+
+        T::default()
+    }
+
+    fn check_remaining_bits(&self, count: usize) -> Result<(), OutOfBounds> {
+        if self.get_position() + count > self.buf.len() * 8 {
+            return Err(OutOfBounds);
+        }
+
+        Ok(())
+    }
+}
+
+/// A read went past the end of the buffer.
+#[cfg(feature = "checked-buffer")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds;
+
+#[cfg(feature = "checked-buffer")]
+impl std::error::Error for OutOfBounds {}
+
+#[cfg(feature = "checked-buffer")]
+impl std::fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "read past the end of the buffer")
+    }
+}
+
+/// Packs the 7 bit groups of the little-endian leb128 bytes in `word`.
+#[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+#[inline]
+fn leb128_value(word: u64) -> u64 {
+    // SAFETY: the bmi2 target feature is enabled at compile time.
+    unsafe { std::arch::x86_64::_pext_u64(word, 0x7f7f_7f7f_7f7f_7f7f) }
+}
+
+/// Packs the 7 bit groups of the little-endian leb128 bytes in `word`.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+#[inline]
+fn leb128_value(word: u64) -> u64 {
+    let mut value = word & 0x7f7f_7f7f_7f7f_7f7f;
+    value = (value & 0x007f_007f_007f_007f) | ((value & 0x7f00_7f00_7f00_7f00) >> 1);
+    value = (value & 0x0000_3fff_0000_3fff) | ((value & 0x3fff_0000_3fff_0000) >> 2);
+    (value & 0x0000_0000_0fff_ffff) | ((value & 0x0fff_ffff_0000_0000) >> 4)
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-recenter-function
+#[inline]
+fn inverse_recenter(r: u32, v: u32) -> u32 {
+    if v > 2 * r {
+        v
+    } else if v & 1 == 1 {
+        r - ((v + 1) >> 1)
+    } else {
+        r + (v >> 1)
     }
 }
 
@@ -144,6 +477,11 @@ impl<'a> Buffer<'a> {
     }
 
     fn next(&mut self) -> bool {
+        #[cfg(feature = "checked-buffer")]
+        if self.index == self.buf.len() {
+            return self.out_of_bounds();
+        }
+
         let curr_byte = self.buf[self.index];
         let shift = 7 - self.bit_pos;
         let bit = curr_byte & (1 << shift);
@@ -158,3 +496,27 @@ impl<'a> AsMut<Buffer<'a>> for Buffer<'a> {
         self
     }
 }
+
+/// Bitstream that owns its bytes, see `Buffer::new_owned` and
+/// `Buffer::from_reader`.
+#[derive(Debug, Clone)]
+pub struct OwnedBuffer {
+    bytes: Vec<u8>,
+}
+
+impl OwnedBuffer {
+    /// Returns a `Buffer` positioned at the start of the bitstream.
+    pub fn as_buffer(&self) -> Buffer<'_> {
+        Buffer::new(&self.bytes)
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl From<Vec<u8>> for OwnedBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+}
@@ -187,3 +187,13 @@ pub const INTRA_FILTER_MODES: u8 = 5; // Number of types of intra filtering
 pub const COEFF_CDF_Q_CTXS: u8 = 4; // Number of selectable context types for the coeff( ) syntax structure
 pub const PRIMARY_REF_NONE: u8 = 7; // Value of primary_ref_frame indicating that there is no primary reference frame
 pub const BUFFER_POOL_MAX_SIZE: u8 = 10; // Number of frames in buffer pool
+
+pub const NONE: i8 = -1; // Invalid reference frame
+pub const INTRA_FRAME: u8 = 0; // Intra frame
+pub const LAST_FRAME: u8 = 1; // Reference frame for inter prediction
+pub const LAST2_FRAME: u8 = 2; // Reference frame for inter prediction
+pub const LAST3_FRAME: u8 = 3; // Reference frame for inter prediction
+pub const GOLDEN_FRAME: u8 = 4; // Reference frame for inter prediction
+pub const BWDREF_FRAME: u8 = 5; // Reference frame for inter prediction
+pub const ALTREF2_FRAME: u8 = 6; // Reference frame for inter prediction
+pub const ALTREF_FRAME: u8 = 7; // Reference frame for inter prediction
@@ -1,4 +1,6 @@
 pub mod buffer;
 pub mod constants;
+mod math;
 pub mod obu;
 pub mod util;
+pub mod writer;
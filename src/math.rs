@@ -0,0 +1,25 @@
+//! Mathematical functions of the specification.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#mathematical-functions
+
+/// FloorLog2( x ), `x` must be greater than 0.
+#[inline]
+pub(crate) fn floor_log2(x: u32) -> u32 {
+    debug_assert!(x > 0);
+
+    31 - x.leading_zeros()
+}
+
+/// The smallest `k` such that `blk_size << k` is greater than or equal to
+/// `target`.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#tile-size-calculation-function
+#[inline]
+pub(crate) fn tile_log2(blk_size: u32, target: u32) -> u32 {
+    let mut k = 0;
+    while (blk_size << k) < target {
+        k += 1;
+    }
+
+    k
+}
@@ -0,0 +1,206 @@
+use super::{Obu, ObuError, ObuHeader, ObuParser, ObuType, read_leb128};
+
+use crate::buffer::Buffer;
+
+/// The two AV1 bitstream formats, see `ObuParser::detect_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitstreamFormat {
+    /// OBUs carrying `obu_size`, one after the other.
+    LowOverhead,
+    /// Length delimited temporal units, frame units and OBUs.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#length-delimited-bitstream-syntax
+    AnnexB,
+    /// The start of the bitstream is valid in neither format.
+    Unknown,
+}
+
+impl ObuParser {
+    /// Guesses the format of the bitstream from the OBUs at the start of
+    /// `buf`, without consuming anything. A low overhead bitstream starts
+    /// with an OBU header with `obu_has_size_field` set, an Annex B one with
+    /// the `temporal_unit_size`, `frame_unit_size` and `obu_length` of its
+    /// first OBU.
+    ///
+    /// Note: When the start of the bitstream is valid in both formats, the
+    /// one whose first OBU is a temporal delimiter wins, low overhead
+    /// otherwise.
+    pub fn detect_format(buf: &Buffer) -> BitstreamFormat {
+        match (probe_low_overhead(buf.clone()), probe_annexb(buf.clone())) {
+            (Some(_), None) => BitstreamFormat::LowOverhead,
+            (None, Some(_)) => BitstreamFormat::AnnexB,
+            (Some(ObuType::TemporalDelimiter), Some(_)) => BitstreamFormat::LowOverhead,
+            (Some(_), Some(ObuType::TemporalDelimiter)) => BitstreamFormat::AnnexB,
+            (Some(_), Some(_)) => BitstreamFormat::LowOverhead,
+            (None, None) => BitstreamFormat::Unknown,
+        }
+    }
+
+    /// Parses one temporal unit of the length delimited (Annex B) bitstream
+    /// format, a `temporal_unit_size` followed by the frame units of the
+    /// temporal unit, and returns all of its OBUs.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#length-delimited-bitstream-syntax
+    pub fn parse_annexb_temporal_unit(&mut self, buf: &mut Buffer) -> Result<Vec<Obu>, ObuError> {
+        if buf.remaining_bytes() == 0 {
+            return Err(ObuError::UnexpectedEof);
+        }
+
+        // temporal_unit_size	leb128()
+        let mut size = read_leb128(buf)? as usize;
+        if size > buf.remaining_bytes() {
+            return Err(ObuError::UnexpectedEof);
+        }
+
+        let mut obus = Vec::new();
+        while size > 0 {
+            let start_position = buf.get_position();
+
+            // frame_unit_size	leb128()
+            let frame_unit_size = read_leb128(buf)? as usize;
+
+            // The frame unit must end within the temporal unit.
+            let unit_size = (buf.get_position() - start_position) / 8 + frame_unit_size;
+            if unit_size > size {
+                return Err(ObuError::InvalidObuLength);
+            }
+
+            obus.extend(self.parse_annexb_frame_unit_obus(buf, frame_unit_size)?);
+            size -= unit_size;
+        }
+
+        Ok(obus)
+    }
+
+    /// Parses a frame unit of the length delimited (Annex B) bitstream format,
+    /// a `frame_unit_size` followed by the `obu_length` prefixed OBUs of one
+    /// frame.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#length-delimited-bitstream-syntax
+    pub fn parse_annexb_frame_unit(&mut self, buf: &mut Buffer) -> Result<Vec<Obu>, ObuError> {
+        // frame_unit_size	leb128()
+        let size = read_leb128(buf)? as usize;
+        if size > buf.remaining_bytes() {
+            return Err(ObuError::UnexpectedEof);
+        }
+
+        self.parse_annexb_frame_unit_obus(buf, size)
+    }
+
+    fn parse_annexb_frame_unit_obus(
+        &mut self,
+        buf: &mut Buffer,
+        mut size: usize,
+    ) -> Result<Vec<Obu>, ObuError> {
+        let mut obus = Vec::new();
+        while size > 0 {
+            let start_position = buf.get_position();
+
+            // obu_length	leb128()
+            let obu_length = read_leb128(buf)? as usize;
+
+            // The OBU must end within the frame unit.
+            let unit_size = (buf.get_position() - start_position) / 8 + obu_length;
+            if unit_size > size {
+                return Err(ObuError::InvalidObuLength);
+            }
+
+            obus.push(self.parse_with_length(buf, obu_length)?);
+            size -= unit_size;
+        }
+
+        Ok(obus)
+    }
+}
+
+/// Returns the type of the first OBU if it and the header of the next one
+/// are valid in the low overhead format.
+fn probe_low_overhead(mut buf: Buffer) -> Option<ObuType> {
+    let header = probe_obu_header(&mut buf)?;
+    if !header.has_size {
+        return None;
+    }
+
+    // obu_size	leb128()
+    let size = buf.get_leb128()? as usize;
+    if size > buf.remaining_bytes() || (header.r#type == ObuType::TemporalDelimiter && size > 0) {
+        return None;
+    }
+
+    buf.seek_bits(size * 8);
+    if buf.remaining_bytes() > 0 && !probe_obu_header(&mut buf)?.has_size {
+        return None;
+    }
+
+    Some(header.r#type)
+}
+
+/// Returns the type of the first OBU if the sizes in front of it and its
+/// header are valid in the Annex B format.
+fn probe_annexb(mut buf: Buffer) -> Option<ObuType> {
+    // temporal_unit_size	leb128()
+    let temporal_unit_size = buf.get_leb128()? as usize;
+    if temporal_unit_size == 0 || temporal_unit_size > buf.remaining_bytes() {
+        return None;
+    }
+
+    // frame_unit_size	leb128()
+    let frame_unit_size = buf.get_leb128()? as usize;
+    if frame_unit_size == 0 || frame_unit_size > buf.remaining_bytes() {
+        return None;
+    }
+
+    // obu_length	leb128()
+    let obu_length = buf.get_leb128()? as usize;
+    if obu_length == 0 || obu_length > frame_unit_size || obu_length > buf.remaining_bytes() {
+        return None;
+    }
+
+    let start_position = buf.get_position();
+    let header = probe_obu_header(&mut buf)?;
+    let payload_size = if header.has_size {
+        // obu_size	leb128()
+        buf.get_leb128()? as usize
+    } else {
+        0
+    };
+
+    // obu_size, when present, must match obu_length, and a temporal
+    // delimiter has an empty payload.
+    let header_size = (buf.get_position() - start_position) / 8;
+    let consistent = if header.has_size {
+        header_size + payload_size == obu_length
+    } else {
+        header_size <= obu_length
+    };
+
+    if !consistent || (header.r#type == ObuType::TemporalDelimiter && obu_length != header_size) {
+        return None;
+    }
+
+    Some(header.r#type)
+}
+
+/// Decodes an OBU header whose forbidden and reserved bits are 0 and whose
+/// type is not reserved.
+fn probe_obu_header(buf: &mut Buffer) -> Option<ObuHeader> {
+    if buf.remaining_bytes() == 0 {
+        return None;
+    }
+
+    let byte = buf.clone().get_bits(8);
+    if byte & 0x81 != 0 || (byte & 0x04 != 0 && buf.remaining_bytes() < 2) {
+        return None;
+    }
+
+    let header = ObuHeader::decode(buf).ok()?;
+    if matches!(header.r#type, ObuType::Reserved(_))
+        || header
+            .extension
+            .is_some_and(|it| it.extension_header_reserved_3bits != 0)
+    {
+        return None;
+    }
+
+    Some(header)
+}
@@ -1,12 +1,58 @@
 use crate::buffer::Buffer;
 
-use super::{ObuContext, ObuError};
+use super::{
+    frame_header::{FrameHeader, FrameType},
+    tile_group::TileGroup,
+    ObuContext, ObuError, ParseMode,
+};
 
+/// see: https://aomediacodec.github.io/av1-spec/#frame-obu-syntax
 #[derive(Debug, Clone)]
-pub struct Frame {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Frame {
+    pub header: FrameHeader,
+    pub tile_group: TileGroup,
+}
 
 impl Frame {
-    pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
-        todo!()
+    pub fn frame_type(&self) -> FrameType {
+        self.header.frame_type()
+    }
+
+    pub fn show_frame(&self) -> bool {
+        self.header.show_frame()
+    }
+
+    /// The coded bytes of the tiles of the frame, `source` is the buffer the
+    /// frame was parsed from, see `TileGroup::tile_data`.
+    pub fn tile_data<'a>(&self, source: &'a [u8]) -> &'a [u8] {
+        self.tile_group.tile_data(source)
+    }
+
+    pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer, size: usize) -> Result<Self, ObuError> {
+        let start_position = buf.get_position();
+        let header = FrameHeader::decode(ctx, buf)?;
+        buf.byte_alignment();
+
+        // The tile group takes whatever is left of the OBU after the frame
+        // header.
+        let header_bytes = (buf.get_position() - start_position) / 8;
+        let tile_group = TileGroup::decode(ctx, buf, size.saturating_sub(header_bytes))?;
+
+        // The frame header sets SeenFrameHeader and the tile group resets it
+        // once the last tile is read. tile_start_and_end_present_flag must be
+        // 0 in a frame OBU, so the frame always ends with it, otherwise the
+        // next OBUs are still checked against this frame header.
+        if !tile_group.frame_tiles_complete && ctx.mode == ParseMode::Strict {
+            return Err(ObuError::InvalidTileRange);
+        }
+
+        Ok(Self { header, tile_group })
+    }
+}
+
+impl std::fmt::Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Frame {}", self.header)
     }
 }
@@ -1,13 +1,23 @@
 use super::{
-    sequence_header::FrameIdNumbersPresent, Buffer, ObuContext, ObuError, ObuUnknownError,
+    sequence_header::{FrameIdNumbersPresent, SequenceHeader, TimingInfo}, Buffer, ObuContext,
+    ObuError, ObuUnknownError, ParseMode,
 };
 
-use crate::constants::{
-    NUM_REF_FRAMES, PRIMARY_REF_NONE, REFS_PER_FRAME, SELECT_INTEGER_MV,
-    SELECT_SCREEN_CONTENT_TOOLS, SUPERRES_DENOM_BITS, SUPERRES_DENOM_MIN, SUPERRES_NUM,
+use crate::{
+    constants::{
+        ALTREF_FRAME, ALTREF2_FRAME, GM_ABS_ALPHA_BITS, GM_ABS_TRANS_BITS, GM_ABS_TRANS_ONLY_BITS,
+        GM_ALPHA_PREC_BITS, GM_TRANS_ONLY_PREC_BITS, GM_TRANS_PREC_BITS, GOLDEN_FRAME,
+        INTRA_FRAME, LAST_FRAME, MAX_LOOP_FILTER, MAX_SEGMENTS, MAX_TILE_AREA, MAX_TILE_COLS,
+        MAX_TILE_ROWS, MAX_TILE_WIDTH, NUM_REF_FRAMES, PRIMARY_REF_NONE, REFS_PER_FRAME,
+        RESTORATION_TILESIZE_MAX, SEG_LVL_ALT_Q, SEG_LVL_MAX, SEG_LVL_REF_FRAME,
+        SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS, SUPERRES_DENOM_BITS, SUPERRES_DENOM_MIN,
+        SUPERRES_NUM, TOTAL_REFS_PER_FRAME, WARPEDMODEL_PREC_BITS,
+    },
+    math::{floor_log2, tile_log2},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FrameType {
     KeyFrame,
     InterFrame,
@@ -15,6 +25,17 @@ pub enum FrameType {
     SwitchFrame,
 }
 
+impl std::fmt::Display for FrameType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::KeyFrame => "KEY_FRAME",
+            Self::InterFrame => "INTER_FRAME",
+            Self::InterOnlyFrame => "INTRA_ONLY_FRAME",
+            Self::SwitchFrame => "SWITCH_FRAME",
+        })
+    }
+}
+
 impl TryFrom<u8> for FrameType {
     type Error = ObuError;
 
@@ -24,17 +45,35 @@ impl TryFrom<u8> for FrameType {
             1 => Self::InterFrame,
             2 => Self::InterOnlyFrame,
             3 => Self::SwitchFrame,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::FrameType)),
+            _ => return Err(ObuUnknownError::FrameType.into()),
         })
     }
 }
 
+impl From<FrameType> for u8 {
+    fn from(value: FrameType) -> Self {
+        match value {
+            FrameType::KeyFrame => 0,
+            FrameType::InterFrame => 1,
+            FrameType::InterOnlyFrame => 2,
+            FrameType::SwitchFrame => 3,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TemporalPointInfo {
     pub frame_presentation_time: u32,
 }
 
 impl TemporalPointInfo {
+    /// frame_presentation_time in seconds, it counts display clock ticks
+    /// (DispCT) from the removal time of the last random access point.
+    pub fn presentation_time(&self, timing_info: &TimingInfo) -> Option<f64> {
+        Some(self.frame_presentation_time as f64 * timing_info.display_clock_tick()?)
+    }
+
     pub fn decode(buf: &mut Buffer, frame_presentation_time_length: usize) -> Self {
         // frame_presentation_time	f(n)
         Self {
@@ -43,10 +82,12 @@ impl TemporalPointInfo {
     }
 }
 
+/// Derives MiCols/MiRows from the coded frame size, with superres this is
+/// the downscaled width and not UpscaledWidth.
 #[inline]
 pub fn compute_image_size(ctx: &mut ObuContext) {
-    ctx.mi_cols = 2 * ((ctx.frame_width + 7) >> 3) as u32;
-    ctx.mi_rows = 2 * ((ctx.frame_height + 7) >> 3) as u32;
+    ctx.mi_cols = 2 * ((ctx.frame_width + 7) >> 3);
+    ctx.mi_rows = 2 * ((ctx.frame_height + 7) >> 3);
 }
 
 #[inline]
@@ -59,15 +100,12 @@ pub fn frame_size(ctx: &mut ObuContext, frame_size_override: bool, buf: &mut Buf
     let (width, height) = if frame_size_override {
         (
             // frame_width_minus_1	f(n)
-            buf.get_bits(sequence_header.frame_width_bits as usize) as u16 + 1,
+            buf.get_bits(sequence_header.frame_width_bits as usize) + 1,
             // frame_height_minus_1	f(n)
-            buf.get_bits(sequence_header.frame_height_bits as usize) as u16 + 1,
+            buf.get_bits(sequence_header.frame_height_bits as usize) + 1,
         )
     } else {
-        (
-            sequence_header.max_frame_width,
-            sequence_header.max_frame_height,
-        )
+        (sequence_header.max_width(), sequence_header.max_height())
     };
 
     ctx.frame_width = width;
@@ -93,15 +131,18 @@ pub fn superres_params(ctx: &mut ObuContext, buf: &mut Buffer) {
 
     ctx.superres_denom = if use_superres {
         // coded_denom	f(SUPERRES_DENOM_BITS)
-        let coded_denom = buf.get_bits(SUPERRES_DENOM_BITS as usize) as u8;
+        let coded_denom = buf.get_bits_u8(SUPERRES_DENOM_BITS as usize);
         coded_denom + SUPERRES_DENOM_MIN
     } else {
         SUPERRES_NUM
     };
 
+    // coded_denom has SUPERRES_DENOM_BITS bits, the denominator is always in
+    // SUPERRES_DENOM_MIN..=SUPERRES_NUM * 2 and the coded width never
+    // exceeds the upscaled width.
+    let denom = ctx.superres_denom as u32;
     ctx.upscaled_width = ctx.frame_width;
-    ctx.frame_width = (ctx.upscaled_width * SUPERRES_NUM as u16 + (ctx.superres_denom as u16 / 2))
-        / ctx.superres_denom as u16;
+    ctx.frame_width = (ctx.upscaled_width * SUPERRES_NUM as u32 + denom / 2) / denom;
 }
 
 #[inline]
@@ -111,9 +152,9 @@ pub fn render_size(ctx: &mut ObuContext, buf: &mut Buffer) {
     let (width, height) = if render_and_frame_size_different {
         (
             // render_width_minus_1	f(16)
-            buf.get_bits(16) as u16 + 1,
+            buf.get_bits(16) + 1,
             // render_height_minus_1	f(16)
-            buf.get_bits(16) as u16 + 1,
+            buf.get_bits(16) + 1,
         )
     } else {
         (ctx.upscaled_width, ctx.frame_height)
@@ -126,18 +167,18 @@ pub fn render_size(ctx: &mut ObuContext, buf: &mut Buffer) {
 #[inline]
 pub fn frame_size_with_refs(ctx: &mut ObuContext, frame_size_override: bool, buf: &mut Buffer) {
     let mut found_ref = false;
-    for _ in 0..REFS_PER_FRAME {
+    for i in 0..REFS_PER_FRAME as usize {
         // found_ref	f(1)
         found_ref = buf.get_bit();
-
-        // if ( found_ref == 1 ) {
-        //     UpscaledWidth = RefUpscaledWidth[ ref_frame_idx[ i ] ]
-        //     FrameWidth = UpscaledWidth
-        //     FrameHeight = RefFrameHeight[ ref_frame_idx[ i ] ]
-        //     RenderWidth = RefRenderWidth[ ref_frame_idx[ i ] ]
-        //     RenderHeight = RefRenderHeight[ ref_frame_idx[ i ] ]
-        //     break
-        // }
+        if found_ref {
+            let ref_frame = ctx.ref_frame_idx[i] as usize;
+            ctx.upscaled_width = ctx.ref_upscaled_width[ref_frame];
+            ctx.frame_width = ctx.upscaled_width;
+            ctx.frame_height = ctx.ref_frame_height[ref_frame];
+            ctx.render_width = ctx.ref_render_width[ref_frame];
+            ctx.render_height = ctx.ref_render_height[ref_frame];
+            break;
+        }
     }
 
     if !found_ref {
@@ -150,6 +191,7 @@ pub fn frame_size_with_refs(ctx: &mut ObuContext, frame_size_override: bool, buf
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum InterpolationFilter {
     Eighttap,
     EighttapSmooth,
@@ -168,7 +210,7 @@ impl TryFrom<u8> for InterpolationFilter {
             2 => Self::EighttapSharp,
             3 => Self::Bilinear,
             4 => Self::Switchable,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::InterpolationFilter)),
+            _ => return Err(ObuUnknownError::InterpolationFilter.into()),
         })
     }
 }
@@ -181,14 +223,1182 @@ pub fn read_interpolation_filter(buf: &mut Buffer) -> Result<InterpolationFilter
         InterpolationFilter::Switchable
     } else {
         // interpolation_filter	f(2)
-        InterpolationFilter::try_from(buf.get_bits(2) as u8)?
+        InterpolationFilter::try_from(buf.get_bits_u8(2))?
     })
 }
 
+/// see: https://aomediacodec.github.io/av1-spec/#loop-restoration-params-semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FrameRestorationType {
+    #[default]
+    None,
+    Wiener,
+    Sgrproj,
+    Switchable,
+}
+
+impl FrameRestorationType {
+    /// Maps the coded `lr_type` syntax element to the restoration type, the
+    /// coded values are ordered differently (Remap_Lr_Type).
+    pub fn from_lr_type(lr_type: u8) -> Result<Self, ObuError> {
+        Ok(match lr_type {
+            0 => Self::None,
+            1 => Self::Switchable,
+            2 => Self::Wiener,
+            3 => Self::Sgrproj,
+            _ => return Err(ObuUnknownError::FrameRestorationType.into()),
+        })
+    }
+}
+
+impl TryFrom<u8> for FrameRestorationType {
+    type Error = ObuError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::None,
+            1 => Self::Wiener,
+            2 => Self::Sgrproj,
+            3 => Self::Switchable,
+            _ => return Err(ObuUnknownError::FrameRestorationType.into()),
+        })
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#frame-reference-mode-semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReferenceMode {
+    #[default]
+    SingleReference,
+    ReferenceSelect,
+}
+
+impl From<bool> for ReferenceMode {
+    fn from(reference_select: bool) -> Self {
+        if reference_select {
+            Self::ReferenceSelect
+        } else {
+            Self::SingleReference
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#frame-reference-mode-syntax
+#[inline]
+pub fn read_frame_reference_mode(ctx: &ObuContext, buf: &mut Buffer) -> ReferenceMode {
+    if ctx.frame_is_intra {
+        ReferenceMode::SingleReference
+    } else {
+        // reference_select	f(1)
+        ReferenceMode::from(buf.get_bit())
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tile-info-syntax
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TileInfo {
+    pub tile_cols_log2: u32,
+    pub tile_rows_log2: u32,
+    pub tile_cols: u32,
+    pub tile_rows: u32,
+    pub mi_col_starts: Vec<u32>,
+    pub mi_row_starts: Vec<u32>,
+    /// The tile whose CDFs are saved at the end of the frame, always 0 when
+    /// the frame has a single tile.
+    pub context_update_tile_id: u32,
+    /// TileSizeBytes, the number of bytes used to code the size of each
+    /// tile, only meaningful when the frame has more than one tile.
+    pub tile_size_bytes: u8,
+}
+
+impl TileInfo {
+    /// NumTiles, the number of tiles in the frame.
+    pub fn num_tiles(&self) -> u32 {
+        self.tile_cols * self.tile_rows
+    }
+
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer) -> Self {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        // a mode info unit is 4x4 luma samples.
+        let sb_size = floor_log2(sequence_header.superblock_size());
+        let sb_shift = sb_size - 2;
+        let sb_cols = (ctx.mi_cols + (1 << sb_shift) - 1) >> sb_shift;
+        let sb_rows = (ctx.mi_rows + (1 << sb_shift) - 1) >> sb_shift;
+
+        let max_tile_width_sb = MAX_TILE_WIDTH as u32 >> sb_size;
+        let max_tile_area_sb = MAX_TILE_AREA >> (2 * sb_size);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        let max_log2_tile_cols = tile_log2(1, sb_cols.min(MAX_TILE_COLS as u32));
+        let max_log2_tile_rows = tile_log2(1, sb_rows.min(MAX_TILE_ROWS as u32));
+        let min_log2_tiles =
+            min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+        // uniform_tile_spacing_flag	f(1)
+        let uniform_tile_spacing = buf.get_bit();
+
+        let (tile_cols_log2, mut mi_col_starts, tile_rows_log2, mut mi_row_starts) =
+            if uniform_tile_spacing {
+                let mut tile_cols_log2 = min_log2_tile_cols;
+                while tile_cols_log2 < max_log2_tile_cols {
+                    // increment_tile_cols_log2	f(1)
+                    if buf.get_bit() {
+                        tile_cols_log2 += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                let tile_width_sb = (sb_cols + (1 << tile_cols_log2) - 1) >> tile_cols_log2;
+                let mi_col_starts = (0..sb_cols)
+                    .step_by(tile_width_sb.max(1) as usize)
+                    .map(|start_sb| start_sb << sb_shift)
+                    .collect::<Vec<_>>();
+
+                let mut tile_rows_log2 = min_log2_tiles.saturating_sub(tile_cols_log2);
+                while tile_rows_log2 < max_log2_tile_rows {
+                    // increment_tile_rows_log2	f(1)
+                    if buf.get_bit() {
+                        tile_rows_log2 += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                let tile_height_sb = (sb_rows + (1 << tile_rows_log2) - 1) >> tile_rows_log2;
+                let mi_row_starts = (0..sb_rows)
+                    .step_by(tile_height_sb.max(1) as usize)
+                    .map(|start_sb| start_sb << sb_shift)
+                    .collect::<Vec<_>>();
+
+                (tile_cols_log2, mi_col_starts, tile_rows_log2, mi_row_starts)
+            } else {
+                let mut widest_tile_sb = 0;
+                let mut mi_col_starts = Vec::new();
+                let mut start_sb = 0;
+                while start_sb < sb_cols {
+                    mi_col_starts.push(start_sb << sb_shift);
+
+                    // width_in_sbs_minus_1	ns(maxWidth)
+                    let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+                    let size_sb = buf.get_ns(max_width) + 1;
+                    widest_tile_sb = widest_tile_sb.max(size_sb);
+                    start_sb += size_sb;
+                }
+
+                let max_tile_area_sb = if min_log2_tiles > 0 {
+                    (sb_rows * sb_cols) >> (min_log2_tiles + 1)
+                } else {
+                    sb_rows * sb_cols
+                };
+
+                let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+
+                let mut mi_row_starts = Vec::new();
+                let mut start_sb = 0;
+                while start_sb < sb_rows {
+                    mi_row_starts.push(start_sb << sb_shift);
+
+                    // height_in_sbs_minus_1	ns(maxHeight)
+                    let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+                    start_sb += buf.get_ns(max_height) + 1;
+                }
+
+                (
+                    tile_log2(1, mi_col_starts.len() as u32),
+                    mi_col_starts,
+                    tile_log2(1, mi_row_starts.len() as u32),
+                    mi_row_starts,
+                )
+            };
+
+        // MiColStarts and MiRowStarts end with the frame size in mode info
+        // units.
+        let tile_cols = mi_col_starts.len() as u32;
+        mi_col_starts.push(ctx.mi_cols);
+
+        let tile_rows = mi_row_starts.len() as u32;
+        mi_row_starts.push(ctx.mi_rows);
+
+        let mut context_update_tile_id = 0;
+        let mut tile_size_bytes = 0;
+        if tile_cols_log2 > 0 || tile_rows_log2 > 0 {
+            // context_update_tile_id	f(TileRowsLog2 + TileColsLog2)
+            context_update_tile_id = buf.get_bits((tile_rows_log2 + tile_cols_log2) as usize);
+
+            // tile_size_bytes_minus_1	f(2)
+            tile_size_bytes = buf.get_bits_u8(2) + 1;
+        }
+
+        Self {
+            tile_cols_log2,
+            tile_rows_log2,
+            tile_cols,
+            tile_rows,
+            mi_col_starts,
+            mi_row_starts,
+            context_update_tile_id,
+            tile_size_bytes,
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#delta-quantizer-syntax
+#[inline]
+pub fn read_delta_q(buf: &mut Buffer) -> i32 {
+    // delta_coded	f(1)
+    if buf.get_bit() {
+        // delta_q	su(1+6)
+        buf.get_su(1 + 6)
+    } else {
+        0
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#quantization-params-syntax
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QuantizationParams {
+    pub base_q_idx: u8,
+    pub delta_q_y_dc: i32,
+    pub delta_q_u_dc: i32,
+    pub delta_q_u_ac: i32,
+    pub delta_q_v_dc: i32,
+    pub delta_q_v_ac: i32,
+    pub using_qmatrix: bool,
+    /// The quantizer matrix levels of each plane, 0 when `using_qmatrix` is
+    /// not set. qm_v is only coded with separate_uv_delta_q, it is qm_u
+    /// otherwise.
+    pub qm_y: u8,
+    pub qm_u: u8,
+    pub qm_v: u8,
+}
+
+impl QuantizationParams {
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer) -> Self {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        let separate_uv_delta_q = sequence_header.color_config.separate_uv_delta_q;
+
+        // base_q_idx	f(8)
+        let base_q_idx = buf.get_bits_u8(8);
+        let delta_q_y_dc = read_delta_q(buf);
+
+        let mut params = Self {
+            base_q_idx,
+            delta_q_y_dc,
+            ..Default::default()
+        };
+
+        if ctx.num_planes > 1 {
+            let diff_uv_delta = if separate_uv_delta_q {
+                // diff_uv_delta	f(1)
+                buf.get_bit()
+            } else {
+                false
+            };
+
+            params.delta_q_u_dc = read_delta_q(buf);
+            params.delta_q_u_ac = read_delta_q(buf);
+            if diff_uv_delta {
+                params.delta_q_v_dc = read_delta_q(buf);
+                params.delta_q_v_ac = read_delta_q(buf);
+            } else {
+                params.delta_q_v_dc = params.delta_q_u_dc;
+                params.delta_q_v_ac = params.delta_q_u_ac;
+            }
+        }
+
+        // using_qmatrix	f(1)
+        params.using_qmatrix = buf.get_bit();
+        if params.using_qmatrix {
+            // qm_y	f(4)
+            params.qm_y = buf.get_bits_u8(4);
+
+            // qm_u	f(4)
+            params.qm_u = buf.get_bits_u8(4);
+
+            params.qm_v = if separate_uv_delta_q {
+                // qm_v	f(4)
+                buf.get_bits_u8(4)
+            } else {
+                params.qm_u
+            };
+        }
+
+        params
+    }
+}
+
+/// Segmentation_Feature_Bits
+const SEGMENTATION_FEATURE_BITS: [usize; SEG_LVL_MAX as usize] = [8, 6, 6, 6, 6, 3, 0, 0];
+
+/// Segmentation_Feature_Signed
+const SEGMENTATION_FEATURE_SIGNED: [bool; SEG_LVL_MAX as usize] =
+    [true, true, true, true, true, false, false, false];
+
+/// Segmentation_Feature_Max
+const SEGMENTATION_FEATURE_MAX: [i32; SEG_LVL_MAX as usize] = [
+    255,
+    MAX_LOOP_FILTER as i32,
+    MAX_LOOP_FILTER as i32,
+    MAX_LOOP_FILTER as i32,
+    MAX_LOOP_FILTER as i32,
+    7,
+    0,
+    0,
+];
+
+/// see: https://aomediacodec.github.io/av1-spec/#segmentation-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SegmentationParams {
+    pub segmentation_enabled: bool,
+    pub segmentation_update_map: bool,
+    pub segmentation_temporal_update: bool,
+    pub segmentation_update_data: bool,
+    /// FeatureData of each segment and feature, `None` for the features that
+    /// are not enabled (FeatureEnabled).
+    pub feature_data: [[Option<i16>; SEG_LVL_MAX as usize]; MAX_SEGMENTS as usize],
+    pub seg_id_pre_skip: bool,
+    pub last_active_seg_id: u8,
+}
+
+impl SegmentationParams {
+    /// The quantizer index of a segment, ignoring the block level delta q
+    /// (get_qindex( 1, segmentId )).
+    pub fn qindex(&self, base_q_idx: u8, segment_id: usize) -> u8 {
+        match self.feature_data[segment_id][SEG_LVL_ALT_Q as usize] {
+            Some(data) => (base_q_idx as i32 + data as i32).clamp(0, 255) as u8,
+            None => base_q_idx,
+        }
+    }
+
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer, primary_ref_frame: u8) -> Self {
+        // Without a primary reference frame everything starts from scratch
+        // (setup_past_independence), otherwise the features of the reference
+        // frame are kept unless they are updated (load_previous).
+        let mut params = if primary_ref_frame == PRIMARY_REF_NONE {
+            Self::default()
+        } else {
+            let ref_frame = ctx.ref_frame_idx[primary_ref_frame as usize] as usize;
+            Self {
+                feature_data: ctx.ref_segmentation_params[ref_frame].feature_data,
+                ..Default::default()
+            }
+        };
+
+        // segmentation_enabled	f(1)
+        params.segmentation_enabled = buf.get_bit();
+        if params.segmentation_enabled {
+            if primary_ref_frame == PRIMARY_REF_NONE {
+                params.segmentation_update_map = true;
+                params.segmentation_update_data = true;
+            } else {
+                // segmentation_update_map	f(1)
+                params.segmentation_update_map = buf.get_bit();
+                if params.segmentation_update_map {
+                    // segmentation_temporal_update	f(1)
+                    params.segmentation_temporal_update = buf.get_bit();
+                }
+
+                // segmentation_update_data	f(1)
+                params.segmentation_update_data = buf.get_bit();
+            }
+
+            if params.segmentation_update_data {
+                for segment in params.feature_data.iter_mut() {
+                    for (j, feature) in segment.iter_mut().enumerate() {
+                        let bits = SEGMENTATION_FEATURE_BITS[j];
+                        let limit = SEGMENTATION_FEATURE_MAX[j];
+
+                        // feature_enabled	f(1)
+                        *feature = if buf.get_bit() {
+                            Some(if SEGMENTATION_FEATURE_SIGNED[j] {
+                                // feature_value	su(1+bitsToRead)
+                                buf.get_su(1 + bits).clamp(-limit, limit) as i16
+                            } else {
+                                // feature_value	f(bitsToRead)
+                                (buf.get_bits(bits) as i32).min(limit) as i16
+                            })
+                        } else {
+                            None
+                        };
+                    }
+                }
+            }
+        } else {
+            params.feature_data = Default::default();
+        }
+
+        for (i, segment) in params.feature_data.iter().enumerate() {
+            for (j, feature) in segment.iter().enumerate() {
+                if feature.is_some() {
+                    params.last_active_seg_id = i as u8;
+                    if j >= SEG_LVL_REF_FRAME as usize {
+                        params.seg_id_pre_skip = true;
+                    }
+                }
+            }
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#delta-quantizer-params-syntax
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeltaQParams {
+    pub delta_q_present: bool,
+    /// delta_q_res, the log2 of the delta q scale.
+    pub delta_q_res: u8,
+}
+
+impl DeltaQParams {
+    pub fn decode(buf: &mut Buffer, base_q_idx: u8) -> Self {
+        let mut params = Self::default();
+        if base_q_idx > 0 {
+            // delta_q_present	f(1)
+            params.delta_q_present = buf.get_bit();
+        }
+
+        if params.delta_q_present {
+            // delta_q_res	f(2)
+            params.delta_q_res = buf.get_bits_u8(2);
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#delta-loop-filter-params-syntax
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeltaLfParams {
+    pub delta_lf_present: bool,
+    /// delta_lf_res, the log2 of the delta loop filter scale.
+    pub delta_lf_res: u8,
+    pub delta_lf_multi: bool,
+}
+
+impl DeltaLfParams {
+    pub fn decode(buf: &mut Buffer, delta_q_present: bool, allow_intrabc: bool) -> Self {
+        let mut params = Self::default();
+        if delta_q_present {
+            if !allow_intrabc {
+                // delta_lf_present	f(1)
+                params.delta_lf_present = buf.get_bit();
+            }
+
+            if params.delta_lf_present {
+                // delta_lf_res	f(2)
+                params.delta_lf_res = buf.get_bits_u8(2);
+                // delta_lf_multi	f(1)
+                params.delta_lf_multi = buf.get_bit();
+            }
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#loop-filter-params-syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LoopFilterParams {
+    /// loop_filter_level, the vertical and horizontal luma levels followed
+    /// by the U and V levels. The chroma levels are only coded when a luma
+    /// level is not 0.
+    pub loop_filter_level: [u8; 4],
+    pub loop_filter_sharpness: u8,
+    pub loop_filter_delta_enabled: bool,
+    pub loop_filter_delta_update: bool,
+    /// loop_filter_ref_deltas, indexed by `INTRA_FRAME..=ALTREF_FRAME`.
+    pub loop_filter_ref_deltas: [i8; TOTAL_REFS_PER_FRAME as usize],
+    pub loop_filter_mode_deltas: [i8; 2],
+}
+
+impl Default for LoopFilterParams {
+    /// The deltas set up by setup_past_independence, every level is 0.
+    fn default() -> Self {
+        let mut loop_filter_ref_deltas = [0; TOTAL_REFS_PER_FRAME as usize];
+        loop_filter_ref_deltas[INTRA_FRAME as usize] = 1;
+        loop_filter_ref_deltas[GOLDEN_FRAME as usize] = -1;
+        loop_filter_ref_deltas[ALTREF2_FRAME as usize] = -1;
+        loop_filter_ref_deltas[ALTREF_FRAME as usize] = -1;
+
+        Self {
+            loop_filter_level: [0; 4],
+            loop_filter_sharpness: 0,
+            loop_filter_delta_enabled: false,
+            loop_filter_delta_update: false,
+            loop_filter_ref_deltas,
+            loop_filter_mode_deltas: [0; 2],
+        }
+    }
+}
+
+impl LoopFilterParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        primary_ref_frame: u8,
+        allow_intrabc: bool,
+    ) -> Self {
+        // The loop filter is off for lossless and intrabc frames, no bits are
+        // coded.
+        if ctx.coded_lossless || allow_intrabc {
+            return Self::default();
+        }
+
+        // Without a primary reference frame the deltas start from their
+        // defaults (setup_past_independence), otherwise from the deltas of the
+        // reference frame (load_previous).
+        let mut params = Self::default();
+        if primary_ref_frame != PRIMARY_REF_NONE {
+            let ref_frame = ctx.ref_frame_idx[primary_ref_frame as usize] as usize;
+            let ref_params = &ctx.ref_loop_filter_params[ref_frame];
+            params.loop_filter_ref_deltas = ref_params.loop_filter_ref_deltas;
+            params.loop_filter_mode_deltas = ref_params.loop_filter_mode_deltas;
+        }
+
+        // loop_filter_level[ 0 ]	f(6)
+        params.loop_filter_level[0] = buf.get_bits_u8(6);
+
+        // loop_filter_level[ 1 ]	f(6)
+        params.loop_filter_level[1] = buf.get_bits_u8(6);
+
+        let luma_levels = params.loop_filter_level[0] != 0 || params.loop_filter_level[1] != 0;
+        if ctx.num_planes > 1 && luma_levels {
+            // loop_filter_level[ 2 ]	f(6)
+            params.loop_filter_level[2] = buf.get_bits_u8(6);
+
+            // loop_filter_level[ 3 ]	f(6)
+            params.loop_filter_level[3] = buf.get_bits_u8(6);
+        }
+
+        // loop_filter_sharpness	f(3)
+        params.loop_filter_sharpness = buf.get_bits_u8(3);
+
+        // loop_filter_delta_enabled	f(1)
+        params.loop_filter_delta_enabled = buf.get_bit();
+        if params.loop_filter_delta_enabled {
+            // loop_filter_delta_update	f(1)
+            params.loop_filter_delta_update = buf.get_bit();
+            if params.loop_filter_delta_update {
+                for delta in params.loop_filter_ref_deltas.iter_mut() {
+                    // update_ref_delta	f(1)
+                    if buf.get_bit() {
+                        // loop_filter_ref_deltas[ i ]	su(1+6)
+                        *delta = buf.get_su(1 + 6) as i8;
+                    }
+                }
+
+                for delta in params.loop_filter_mode_deltas.iter_mut() {
+                    // update_mode_delta	f(1)
+                    if buf.get_bit() {
+                        // loop_filter_mode_deltas[ i ]	su(1+6)
+                        *delta = buf.get_su(1 + 6) as i8;
+                    }
+                }
+            }
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#cdef-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CdefParams {
+    /// CdefDamping
+    pub cdef_damping: u8,
+    pub cdef_bits: u8,
+    /// The strengths of each of the `1 << cdef_bits` presets, the secondary
+    /// strengths are stored after the 3 to 4 adjustment.
+    pub cdef_y_pri_strength: [u8; 8],
+    pub cdef_y_sec_strength: [u8; 8],
+    pub cdef_uv_pri_strength: [u8; 8],
+    pub cdef_uv_sec_strength: [u8; 8],
+}
+
+impl CdefParams {
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer, allow_intrabc: bool) -> Self {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        // CDEF is off, a single preset with all strengths equal to 0.
+        if ctx.coded_lossless || allow_intrabc || !sequence_header.enable_cdef {
+            return Self {
+                cdef_damping: 3,
+                ..Default::default()
+            };
+        }
+
+        let mut params = Self {
+            // cdef_damping_minus_3	f(2)
+            cdef_damping: buf.get_bits_u8(2) + 3,
+            // cdef_bits	f(2)
+            cdef_bits: buf.get_bits_u8(2),
+            ..Default::default()
+        };
+
+        let sec_strength = |strength: u8| if strength == 3 { 4 } else { strength };
+        for i in 0..1 << params.cdef_bits {
+            // cdef_y_pri_strength[ i ]	f(4)
+            params.cdef_y_pri_strength[i] = buf.get_bits_u8(4);
+
+            // cdef_y_sec_strength[ i ]	f(2)
+            params.cdef_y_sec_strength[i] = sec_strength(buf.get_bits_u8(2));
+
+            if ctx.num_planes > 1 {
+                // cdef_uv_pri_strength[ i ]	f(4)
+                params.cdef_uv_pri_strength[i] = buf.get_bits_u8(4);
+
+                // cdef_uv_sec_strength[ i ]	f(2)
+                params.cdef_uv_sec_strength[i] = sec_strength(buf.get_bits_u8(2));
+            }
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#loop-restoration-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LrParams {
+    /// FrameRestorationType of each plane.
+    pub frame_restoration_type: [FrameRestorationType; 3],
+    /// LoopRestorationSize of each plane, 0 when loop restoration is not
+    /// used.
+    pub loop_restoration_size: [u16; 3],
+}
+
+impl LrParams {
+    /// UsesLr
+    pub fn uses_lr(&self) -> bool {
+        self.frame_restoration_type
+            .iter()
+            .any(|it| *it != FrameRestorationType::None)
+    }
+
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        allow_intrabc: bool,
+    ) -> Result<Self, ObuError> {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        let mut params = Self::default();
+        if ctx.all_lossless || allow_intrabc || !sequence_header.enable_restoration {
+            return Ok(params);
+        }
+
+        for i in 0..ctx.num_planes as usize {
+            // lr_type	f(2)
+            params.frame_restoration_type[i] =
+                FrameRestorationType::from_lr_type(buf.get_bits_u8(2))?;
+        }
+
+        if params.uses_lr() {
+            // lr_unit_shift	f(1)
+            let mut lr_unit_shift = buf.get_bits_u8(1);
+            if sequence_header.use_128x128_superblock {
+                lr_unit_shift += 1;
+            } else if lr_unit_shift == 1 {
+                // lr_unit_extra_shift	f(1)
+                lr_unit_shift += buf.get_bits_u8(1);
+            }
+
+            let color_config = &sequence_header.color_config;
+            let uses_chroma_lr = params.frame_restoration_type[1..]
+                .iter()
+                .any(|it| *it != FrameRestorationType::None);
+
+            let lr_uv_shift =
+                if color_config.subsampling_x && color_config.subsampling_y && uses_chroma_lr {
+                    // lr_uv_shift	f(1)
+                    buf.get_bits_u8(1)
+                } else {
+                    0
+                };
+
+            let size = RESTORATION_TILESIZE_MAX >> (2 - lr_unit_shift);
+            params.loop_restoration_size = [size, size >> lr_uv_shift, size >> lr_uv_shift];
+        }
+
+        Ok(params)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tx-mode-semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TxMode {
+    #[default]
+    Only4x4,
+    Largest,
+    Select,
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tx-mode-syntax
+#[inline]
+pub fn read_tx_mode(ctx: &ObuContext, buf: &mut Buffer) -> TxMode {
+    // Lossless frames only use the 4x4 Walsh-Hadamard transform.
+    if ctx.coded_lossless {
+        TxMode::Only4x4
+    } else if buf.get_bit() {
+        // tx_mode_select	f(1)
+        TxMode::Select
+    } else {
+        TxMode::Largest
+    }
+}
+
+/// GmType, the global motion model of a reference frame.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#global-motion-params-semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum WarpModelType {
+    #[default]
+    Identity,
+    Translation,
+    RotZoom,
+    Affine,
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#global-motion-params-syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalMotionParams {
+    /// GmType, indexed by `LAST_FRAME..=ALTREF_FRAME`.
+    pub gm_type: [WarpModelType; TOTAL_REFS_PER_FRAME as usize],
+    /// gm_params, indexed by `LAST_FRAME..=ALTREF_FRAME`, the warp matrix
+    /// entries with `WARPEDMODEL_PREC_BITS` fractional bits.
+    pub gm_params: [[i32; 6]; TOTAL_REFS_PER_FRAME as usize],
+}
+
+impl Default for GlobalMotionParams {
+    /// The identity model for every reference frame.
+    fn default() -> Self {
+        let mut gm_params = [0; 6];
+        gm_params[2] = 1 << WARPEDMODEL_PREC_BITS;
+        gm_params[5] = 1 << WARPEDMODEL_PREC_BITS;
+
+        Self {
+            gm_type: [WarpModelType::Identity; TOTAL_REFS_PER_FRAME as usize],
+            gm_params: [gm_params; TOTAL_REFS_PER_FRAME as usize],
+        }
+    }
+}
+
+impl GlobalMotionParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        primary_ref_frame: u8,
+        allow_high_precision_mv: bool,
+    ) -> Self {
+        let mut params = Self::default();
+        if ctx.frame_is_intra {
+            return params;
+        }
+
+        // The parameters are coded relative to the identity model
+        // (setup_past_independence) or to the parameters of the reference
+        // frame (load_previous), PrevGmParams.
+        let prev_gm_params = if primary_ref_frame == PRIMARY_REF_NONE {
+            Self::default().gm_params
+        } else {
+            let ref_frame = ctx.ref_frame_idx[primary_ref_frame as usize] as usize;
+            ctx.ref_global_motion_params[ref_frame].gm_params
+        };
+
+        // LAST_FRAME..=ALTREF_FRAME
+        for (ref_frame, prev) in prev_gm_params.iter().enumerate().skip(LAST_FRAME as usize) {
+            // is_global	f(1)
+            let r#type = if !buf.get_bit() {
+                WarpModelType::Identity
+            } else if buf.get_bit() {
+                // is_rot_zoom	f(1)
+                WarpModelType::RotZoom
+            } else if buf.get_bit() {
+                // is_translation	f(1)
+                WarpModelType::Translation
+            } else {
+                WarpModelType::Affine
+            };
+
+            params.gm_type[ref_frame] = r#type;
+
+            // The entries in the order they are coded, the ROTZOOM model
+            // derives the other two.
+            let coded_idx: &[usize] = match r#type {
+                WarpModelType::Identity => &[],
+                WarpModelType::Translation => &[0, 1],
+                WarpModelType::RotZoom => &[2, 3, 0, 1],
+                WarpModelType::Affine => &[2, 3, 4, 5, 0, 1],
+            };
+
+            let gm_params = &mut params.gm_params[ref_frame];
+            for &idx in coded_idx {
+                gm_params[idx] =
+                    read_global_param(buf, r#type, idx, prev[idx], allow_high_precision_mv);
+            }
+
+            if r#type == WarpModelType::RotZoom {
+                gm_params[4] = -gm_params[3];
+                gm_params[5] = gm_params[2];
+            }
+        }
+
+        params
+    }
+}
+
+/// Decodes entry `idx` of the warp matrix of a global motion model of type
+/// `type`, coded relative to `prev_gm_param`, the same entry of PrevGmParams.
+/// The entry is returned with `WARPEDMODEL_PREC_BITS` fractional bits.
+///
+/// The translation entries, 0 and 1, are coded with fewer bits and less
+/// precision than the others, even fewer for a TRANSLATION model without
+/// allow_high_precision_mv.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#global-param-syntax
+pub fn read_global_param(
+    buf: &mut Buffer,
+    r#type: WarpModelType,
+    idx: usize,
+    prev_gm_param: i32,
+    allow_high_precision_mv: bool,
+) -> i32 {
+    let (abs_bits, prec_bits) = if idx < 2 {
+        if r#type == WarpModelType::Translation {
+            let low_precision = !allow_high_precision_mv as u8;
+            (
+                GM_ABS_TRANS_ONLY_BITS - low_precision,
+                GM_TRANS_ONLY_PREC_BITS - low_precision,
+            )
+        } else {
+            (GM_ABS_TRANS_BITS, GM_TRANS_PREC_BITS)
+        }
+    } else {
+        (GM_ABS_ALPHA_BITS, GM_ALPHA_PREC_BITS)
+    };
+
+    let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+    let (round, sub) = if idx % 3 == 2 {
+        (1 << WARPEDMODEL_PREC_BITS, 1 << prec_bits)
+    } else {
+        (0, 0)
+    };
+
+    let mx = 1 << abs_bits;
+    let r = (prev_gm_param >> prec_diff) - sub;
+    (buf.get_signed_subexp_with_ref(-mx, mx + 1, r) << prec_diff) + round
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#film-grain-params-syntax
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FilmGrainParams {
+    pub apply_grain: bool,
+    pub grain_seed: u16,
+    pub update_grain: bool,
+    /// Set when the parameters, except `grain_seed`, are copied from this
+    /// reference slot instead of being coded.
+    pub film_grain_params_ref_idx: Option<u8>,
+    pub point_y_value: Vec<u8>,
+    pub point_y_scaling: Vec<u8>,
+    pub chroma_scaling_from_luma: bool,
+    pub point_cb_value: Vec<u8>,
+    pub point_cb_scaling: Vec<u8>,
+    pub point_cr_value: Vec<u8>,
+    pub point_cr_scaling: Vec<u8>,
+    pub grain_scaling_minus_8: u8,
+    pub ar_coeff_lag: u8,
+    pub ar_coeffs_y_plus_128: Vec<u8>,
+    pub ar_coeffs_cb_plus_128: Vec<u8>,
+    pub ar_coeffs_cr_plus_128: Vec<u8>,
+    pub ar_coeff_shift_minus_6: u8,
+    pub grain_scale_shift: u8,
+    pub cb_mult: u8,
+    pub cb_luma_mult: u8,
+    pub cb_offset: u16,
+    pub cr_mult: u8,
+    pub cr_luma_mult: u8,
+    pub cr_offset: u16,
+    pub overlap_flag: bool,
+    pub clip_to_restricted_range: bool,
+}
+
+impl FilmGrainParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        frame_type: FrameType,
+        show_frame: bool,
+        showable_frame: bool,
+    ) -> Result<Self, ObuError> {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundSequenceHeader)?;
+
+        // reset_grain_params()
+        let mut params = Self::default();
+        if !sequence_header.film_grain_params_present || (!show_frame && !showable_frame) {
+            return Ok(params);
+        }
+
+        // apply_grain	f(1)
+        params.apply_grain = buf.get_bit();
+        if !params.apply_grain {
+            return Ok(params);
+        }
+
+        // grain_seed	f(16)
+        let grain_seed = buf.get_bits_u16(16);
+        params.update_grain = if frame_type == FrameType::InterFrame {
+            // update_grain	f(1)
+            buf.get_bit()
+        } else {
+            true
+        };
+
+        if !params.update_grain {
+            // film_grain_params_ref_idx	f(3)
+            let film_grain_params_ref_idx = buf.get_bits_u8(3);
+            if ctx.mode == ParseMode::Strict
+                && !ctx.ref_frame_idx.contains(&film_grain_params_ref_idx)
+            {
+                return Err(ObuError::FilmGrainParamsRefMismatch);
+            }
+
+            // load_grain_params( film_grain_params_ref_idx ), the seed of this
+            // frame is kept.
+            return Ok(Self {
+                grain_seed,
+                update_grain: false,
+                film_grain_params_ref_idx: Some(film_grain_params_ref_idx),
+                ..ctx.ref_film_grain_params[film_grain_params_ref_idx as usize].clone()
+            });
+        }
+
+        params.grain_seed = grain_seed;
+
+        let color_config = &sequence_header.color_config;
+
+        // num_y_points	f(4)
+        let num_y_points = buf.get_bits(4);
+        for _ in 0..num_y_points {
+            // point_y_value[ i ]	f(8)
+            params.point_y_value.push(buf.get_bits_u8(8));
+
+            // point_y_scaling[ i ]	f(8)
+            params.point_y_scaling.push(buf.get_bits_u8(8));
+        }
+
+        params.chroma_scaling_from_luma = if color_config.mono_chrome {
+            false
+        } else {
+            // chroma_scaling_from_luma	f(1)
+            buf.get_bit()
+        };
+
+        if !(color_config.mono_chrome
+            || params.chroma_scaling_from_luma
+            || (color_config.subsampling_x && color_config.subsampling_y && num_y_points == 0))
+        {
+            // num_cb_points	f(4)
+            for _ in 0..buf.get_bits(4) {
+                // point_cb_value[ i ]	f(8)
+                params.point_cb_value.push(buf.get_bits_u8(8));
+
+                // point_cb_scaling[ i ]	f(8)
+                params.point_cb_scaling.push(buf.get_bits_u8(8));
+            }
+
+            // num_cr_points	f(4)
+            for _ in 0..buf.get_bits(4) {
+                // point_cr_value[ i ]	f(8)
+                params.point_cr_value.push(buf.get_bits_u8(8));
+
+                // point_cr_scaling[ i ]	f(8)
+                params.point_cr_scaling.push(buf.get_bits_u8(8));
+            }
+        }
+
+        // grain_scaling_minus_8	f(2)
+        params.grain_scaling_minus_8 = buf.get_bits_u8(2);
+
+        // ar_coeff_lag	f(2)
+        params.ar_coeff_lag = buf.get_bits_u8(2);
+
+        let num_pos_luma = 2 * params.ar_coeff_lag as usize * (params.ar_coeff_lag as usize + 1);
+        let num_pos_chroma = if num_y_points > 0 {
+            for _ in 0..num_pos_luma {
+                // ar_coeffs_y_plus_128[ i ]	f(8)
+                params.ar_coeffs_y_plus_128.push(buf.get_bits_u8(8));
+            }
+
+            num_pos_luma + 1
+        } else {
+            num_pos_luma
+        };
+
+        if params.chroma_scaling_from_luma || !params.point_cb_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                // ar_coeffs_cb_plus_128[ i ]	f(8)
+                params.ar_coeffs_cb_plus_128.push(buf.get_bits_u8(8));
+            }
+        }
+
+        if params.chroma_scaling_from_luma || !params.point_cr_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                // ar_coeffs_cr_plus_128[ i ]	f(8)
+                params.ar_coeffs_cr_plus_128.push(buf.get_bits_u8(8));
+            }
+        }
+
+        // ar_coeff_shift_minus_6	f(2)
+        params.ar_coeff_shift_minus_6 = buf.get_bits_u8(2);
+
+        // grain_scale_shift	f(2)
+        params.grain_scale_shift = buf.get_bits_u8(2);
+
+        if !params.point_cb_value.is_empty() {
+            // cb_mult	f(8)
+            params.cb_mult = buf.get_bits_u8(8);
+
+            // cb_luma_mult	f(8)
+            params.cb_luma_mult = buf.get_bits_u8(8);
+
+            // cb_offset	f(9)
+            params.cb_offset = buf.get_bits_u16(9);
+        }
+
+        if !params.point_cr_value.is_empty() {
+            // cr_mult	f(8)
+            params.cr_mult = buf.get_bits_u8(8);
+
+            // cr_luma_mult	f(8)
+            params.cr_luma_mult = buf.get_bits_u8(8);
+
+            // cr_offset	f(9)
+            params.cr_offset = buf.get_bits_u16(9);
+        }
+
+        // overlap_flag	f(1)
+        params.overlap_flag = buf.get_bit();
+
+        // clip_to_restricted_range	f(1)
+        params.clip_to_restricted_range = buf.get_bit();
+
+        Ok(params)
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct UncompressedHeader;
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UncompressedHeader {
+    /// Set when show_existing_frame is used, the reference slot of the frame
+    /// being shown. Only `frame_type` and `refresh_frame_flags` are
+    /// meaningful for such headers.
+    pub frame_to_show_map_idx: Option<u8>,
+    pub frame_type: FrameType,
+    pub show_frame: bool,
+    /// Read for shown frames when the decoder model is signaled without an
+    /// equal picture interval.
+    pub temporal_point_info: Option<TemporalPointInfo>,
+    /// display_frame_id, only coded with show_existing_frame.
+    pub display_frame_id: Option<u32>,
+    pub showable_frame: bool,
+    pub error_resilient_mode: bool,
+    /// Always `PRIMARY_REF_NONE` for intra and error resilient frames.
+    pub primary_ref_frame: u8,
+    pub disable_cdf_update: bool,
+    /// Always set when `disable_cdf_update` is set, error resilient frames
+    /// still code it.
+    pub disable_frame_end_update_cdf: bool,
+    /// buffer_removal_time of each operating point, `None` for the operating
+    /// points that don't contain this frame or have no decoder model.
+    pub buffer_removal_times: Vec<Option<u32>>,
+    pub refresh_frame_flags: u32,
+    pub allow_screen_content_tools: bool,
+    pub force_integer_mv: bool,
+    /// UpscaledWidth, the width of the decoded frame.
+    pub upscaled_width: u32,
+    /// FrameWidth, the coded width, smaller than `upscaled_width` with
+    /// superres.
+    pub frame_width: u32,
+    /// FrameHeight
+    pub frame_height: u32,
+    /// Whether the render size differs from the upscaled frame size, the
+    /// render size only tells how the frame is meant to be displayed.
+    pub render_and_frame_size_different: bool,
+    /// RenderWidth
+    pub render_width: u32,
+    /// RenderHeight
+    pub render_height: u32,
+    /// Whether intra blocks can be predicted from the decoded part of the
+    /// frame, only coded for intra frames using screen content tools. The
+    /// in-loop filters are off when it is set.
+    pub allow_intrabc: bool,
+    /// Whether the motion vectors of previous frames are used (temporal MV
+    /// prediction), only coded for inter frames that are not error resilient
+    /// when the sequence enables it.
+    pub use_ref_frame_mvs: bool,
+    pub tile_info: TileInfo,
+    pub quantization_params: QuantizationParams,
+    pub segmentation_params: SegmentationParams,
+    pub delta_q_params: DeltaQParams,
+    pub delta_lf_params: DeltaLfParams,
+    /// CodedLossless, every segment of the frame is coded losslessly.
+    pub coded_lossless: bool,
+    /// AllLossless, the frame is lossless and not upscaled.
+    pub all_lossless: bool,
+    pub loop_filter_params: LoopFilterParams,
+    pub cdef_params: CdefParams,
+    pub lr_params: LrParams,
+    pub tx_mode: TxMode,
+    /// reference_select, whether blocks can use compound prediction.
+    pub reference_select: bool,
+    /// SkipModeFrame, the two reference frames of skip mode when
+    /// skip_mode_present is set.
+    pub skip_mode_frame: Option<[u8; 2]>,
+    pub allow_warped_motion: bool,
+    pub reduced_tx_set: bool,
+    pub global_motion_params: GlobalMotionParams,
+    pub film_grain_params: FilmGrainParams,
+}
 
 impl UncompressedHeader {
+    /// Whether decoding can start at this frame, a shown key frame resets all
+    /// references and a switch frame refreshes every reference slot.
+    pub fn is_random_access_point(&self) -> bool {
+        (self.frame_type == FrameType::KeyFrame && self.show_frame)
+            || self.frame_type == FrameType::SwitchFrame
+    }
+
     pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
         let sequence_header = ctx
             .sequence_header
@@ -196,12 +1406,7 @@ impl UncompressedHeader {
             .cloned()
             .ok_or_else(|| ObuError::NotFoundSequenceHeader)?;
 
-        let mut id_len = 0;
-        if let Some(ref value) = sequence_header.frame_id_numbers_present {
-            id_len = value.additional_frame_id_length as usize
-                + value.delta_frame_id_length as usize
-                + 3;
-        }
+        let id_len = sequence_header.frame_id_length() as usize;
 
         let all_frames = (1 << NUM_REF_FRAMES) - 1;
 
@@ -211,19 +1416,21 @@ impl UncompressedHeader {
         let mut showable_frame = false;
 
         let mut refresh_frame_flags = 0;
-        let mut error_resilient_mode = false;
+        let error_resilient_mode;
         let mut temporal_point_info = None;
         let mut display_frame_id = None;
 
         if sequence_header.reduced_still_picture_header {
+            // A reduced still picture is a single shown key frame.
             ctx.frame_is_intra = true;
+            error_resilient_mode = true;
         } else {
             // show_existing_frame	f(1)
             show_existing_frame = buf.get_bit();
 
             if show_existing_frame {
                 // frame_to_show_map_idx	f(3)
-                let frame_to_show_map_idx = buf.get_bits(3) as u8;
+                let frame_to_show_map_idx = buf.get_bits_u8(3);
                 if let Some(decoder_model_info) = &sequence_header.decoder_model_info {
                     if !sequence_header
                         .timing_info
@@ -242,26 +1449,99 @@ impl UncompressedHeader {
                     display_frame_id = Some(buf.get_bits(id_len));
                 }
 
-                frame_type = *ctx
-                    .ref_frame_type
-                    .get(frame_to_show_map_idx as usize)
-                    .ok_or_else(|| ObuError::Unknown(ObuUnknownError::FrameTypeRefIndex))?;
+                frame_type = ctx.ref_frame_type[frame_to_show_map_idx as usize]
+                    .ok_or(ObuUnknownError::FrameTypeRefIndex)?;
+
+                // Only frames with showable_frame set can be shown again, a
+                // shown key frame never is and a key frame shown this way is
+                // shown at most once.
+                if !ctx.ref_showable_frame[frame_to_show_map_idx as usize]
+                    && ctx.mode == ParseMode::Strict
+                {
+                    return Err(ObuError::FrameNotShowable);
+                }
+
+                // The shown frame keeps the size it was decoded with.
+                let shown_frame = frame_to_show_map_idx as usize;
+                ctx.upscaled_width = ctx.ref_upscaled_width[shown_frame];
+                ctx.frame_width = ctx.ref_frame_width[shown_frame];
+                ctx.frame_height = ctx.ref_frame_height[shown_frame];
+                ctx.render_width = ctx.ref_render_width[shown_frame];
+                ctx.render_height = ctx.ref_render_height[shown_frame];
 
                 if frame_type == FrameType::KeyFrame {
                     refresh_frame_flags = all_frames;
+
+                    // The reference frame loading process restores the order
+                    // hint and the frame id of the shown frame before every
+                    // slot is refreshed with them.
+                    ctx.order_hint = ctx.ref_order_hint[frame_to_show_map_idx as usize];
+                    ctx.current_frame_id = ctx.ref_frame_id[frame_to_show_map_idx as usize];
+                    ctx.segmentation_params =
+                        ctx.ref_segmentation_params[frame_to_show_map_idx as usize];
+                    ctx.loop_filter_params =
+                        ctx.ref_loop_filter_params[frame_to_show_map_idx as usize];
+                    ctx.global_motion_params =
+                        ctx.ref_global_motion_params[frame_to_show_map_idx as usize];
                 }
 
-                if sequence_header.film_grain_params_present {
-                    // TODO
-                    // load_grain_params( frame_to_show_map_idx )
+                // load_grain_params( frame_to_show_map_idx )
+                ctx.film_grain_params = if sequence_header.film_grain_params_present {
+                    ctx.ref_film_grain_params[frame_to_show_map_idx as usize].clone()
+                } else {
+                    FilmGrainParams::default()
+                };
+
+                if frame_type == FrameType::KeyFrame {
+                    ctx.update_ref_frames(refresh_frame_flags, frame_type, false);
                 }
 
-                // TODO
-                // return
+                return Ok(Self {
+                    frame_to_show_map_idx: Some(frame_to_show_map_idx),
+                    frame_type,
+                    show_frame: true,
+                    temporal_point_info,
+                    display_frame_id,
+                    showable_frame: false,
+                    error_resilient_mode: false,
+                    primary_ref_frame: PRIMARY_REF_NONE,
+                    disable_cdf_update: true,
+                    disable_frame_end_update_cdf: true,
+                    buffer_removal_times: vec![None; sequence_header.operating_points.len()],
+                    refresh_frame_flags,
+                    allow_screen_content_tools: false,
+                    force_integer_mv: false,
+                    upscaled_width: ctx.upscaled_width,
+                    frame_width: ctx.frame_width,
+                    frame_height: ctx.frame_height,
+                    render_and_frame_size_different: ctx.render_width != ctx.upscaled_width
+                        || ctx.render_height != ctx.frame_height,
+                    render_width: ctx.render_width,
+                    render_height: ctx.render_height,
+                    allow_intrabc: false,
+                    use_ref_frame_mvs: false,
+                    tile_info: TileInfo::default(),
+                    quantization_params: QuantizationParams::default(),
+                    segmentation_params: SegmentationParams::default(),
+                    delta_q_params: DeltaQParams::default(),
+                    delta_lf_params: DeltaLfParams::default(),
+                    coded_lossless: false,
+                    all_lossless: false,
+                    loop_filter_params: ctx.loop_filter_params,
+                    cdef_params: CdefParams::default(),
+                    lr_params: LrParams::default(),
+                    tx_mode: TxMode::default(),
+                    reference_select: false,
+                    skip_mode_frame: None,
+                    allow_warped_motion: false,
+                    reduced_tx_set: false,
+                    global_motion_params: ctx.global_motion_params,
+                    film_grain_params: ctx.film_grain_params.clone(),
+                });
             }
 
             // frame_type	f(2)
-            frame_type = FrameType::try_from(buf.get_bits(2) as u8)?;
+            frame_type = FrameType::try_from(buf.get_bits_u8(2))?;
             ctx.frame_is_intra =
                 frame_type == FrameType::InterOnlyFrame || frame_type == FrameType::KeyFrame;
 
@@ -298,17 +1578,14 @@ impl UncompressedHeader {
             };
         }
 
-        if frame_type == FrameType::SwitchFrame && show_frame {
+        if frame_type == FrameType::KeyFrame && show_frame {
             for i in 0..NUM_REF_FRAMES as usize {
                 ctx.ref_frame_marking[i] = false;
-                ctx.ref_order_hint[i] = i as u32;
+                ctx.ref_order_hint[i] = 0;
             }
 
-            for i in 0..REFS_PER_FRAME as u8 {
-                // TODO
-                // OrderHints[ LAST_FRAME + i ] = 0
-                //
-                // ctx.ref_order_hints[i] = 0;
+            for i in 0..REFS_PER_FRAME as usize {
+                ctx.order_hints[LAST_FRAME as usize + i] = 0;
             }
         }
 
@@ -337,12 +1614,18 @@ impl UncompressedHeader {
             force_integer_mv = true;
         }
 
-        let current_frame_id = if let Some(f) = &sequence_header.frame_id_numbers_present {
+        ctx.current_frame_id = if let Some(frame_id_numbers_present) =
+            &sequence_header.frame_id_numbers_present
+        {
             // current_frame_id	f(idLen)
-            buf.get_bits(id_len)
-
-            // TODO
-            // mark_ref_frames( idLen )
+            let current_frame_id = buf.get_bits(id_len);
+            ctx.mark_ref_frames(
+                current_frame_id,
+                id_len,
+                frame_id_numbers_present.delta_frame_id_length as usize,
+            );
+
+            current_frame_id
         } else {
             0
         };
@@ -364,28 +1647,33 @@ impl UncompressedHeader {
             PRIMARY_REF_NONE
         } else {
             // primary_ref_frame	f(3)
-            buf.get_bits(3) as u8
+            buf.get_bits_u8(3)
         };
 
-        let mut buffer_removal_times = Vec::with_capacity(sequence_header.operating_points.len());
+        let mut buffer_removal_times = vec![None; sequence_header.operating_points.len()];
         if let Some(decoder_model_info) = sequence_header.decoder_model_info {
             // buffer_removal_time_present_flag	f(1)
             let buffer_removal_time_present_flag = buf.get_bit();
             if buffer_removal_time_present_flag {
-                for operating_point in &sequence_header.operating_points {
+                // Without the extension the OBU belongs to the base layers.
+                let (temporal_id, spatial_id) = ctx
+                    .obu_header_extension
+                    .map(|it| (it.temporal_id, it.spatial_id))
+                    .unwrap_or((0, 0));
+
+                for (op_num, operating_point) in
+                    sequence_header.operating_points.iter().enumerate()
+                {
                     if operating_point.operating_parameters_info.is_some() {
-                        if let Some(extension) = ctx.obu_header_extension {
-                            let op_pt_dic = operating_point.idc;
-                            let in_temporal_layer = ((op_pt_dic >> extension.temporal_id) & 1) != 0;
-                            let in_spatial_layer =
-                                ((op_pt_dic >> (extension.spatial_id + 8)) & 1) != 0;
-
-                            if op_pt_dic == 0 || (in_temporal_layer && in_spatial_layer) {
-                                // buffer_removal_time[ opNum ]	f(n)
-                                buffer_removal_times.push(buf.get_bits(
-                                    decoder_model_info.buffer_removal_time_length as usize,
-                                ));
-                            }
+                        let op_pt_idc = operating_point.idc;
+                        let in_temporal_layer = ((op_pt_idc >> temporal_id) & 1) != 0;
+                        let in_spatial_layer = ((op_pt_idc >> (spatial_id + 8)) & 1) != 0;
+
+                        if op_pt_idc == 0 || (in_temporal_layer && in_spatial_layer) {
+                            // buffer_removal_time[ opNum ]	f(n)
+                            buffer_removal_times[op_num] = Some(buf.get_bits(
+                                decoder_model_info.buffer_removal_time_length as usize,
+                            ));
                         }
                     }
                 }
@@ -416,6 +1704,7 @@ impl UncompressedHeader {
 
                 if ctx.ref_order_hint[i] != hints[i] {
                     ctx.ref_frame_marking[i] = false;
+                    ctx.ref_order_hint[i] = hints[i];
                 }
             }
 
@@ -432,25 +1721,24 @@ impl UncompressedHeader {
             }
         } else {
             let mut frame_refs_short_signaling = false;
-            if !sequence_header.enable_order_hint {
+            if sequence_header.enable_order_hint {
                 // frame_refs_short_signaling	f(1)
                 frame_refs_short_signaling = buf.get_bit();
                 if frame_refs_short_signaling {
                     // last_frame_idx	f(3)
-                    let last_frame_idx = buf.get_bits(3);
+                    let last_frame_idx = buf.get_bits_u8(3);
 
                     // gold_frame_idx	f(3)
-                    let gold_frame_idx = buf.get_bits(3);
+                    let gold_frame_idx = buf.get_bits_u8(3);
 
-                    // TODO
-                    // set_frame_refs()
+                    ctx.set_frame_refs(last_frame_idx, gold_frame_idx);
                 }
             }
 
-            for _ in 0..REFS_PER_FRAME {
-                if frame_refs_short_signaling {
+            for i in 0..REFS_PER_FRAME as usize {
+                if !frame_refs_short_signaling {
                     // ref_frame_idx[ i ]	f(3)
-                    let ref_frame_idx = buf.get_bits(3);
+                    ctx.ref_frame_idx[i] = buf.get_bits_u8(3);
                 }
 
                 if let Some(frame_id_numbers_present) = &sequence_header.frame_id_numbers_present {
@@ -459,8 +1747,13 @@ impl UncompressedHeader {
                     let delta_frame_id = buf.get_bits(n as usize) + 1;
                     ctx.delta_frame_id = delta_frame_id;
 
-                    // expectedFrameId[ i ] = ((current_frame_id + (1 << idLen)
-                    // - DeltaFrameId ) % (1 << idLen))
+                    let expected_frame_id =
+                        (ctx.current_frame_id + (1 << id_len) - delta_frame_id) % (1 << id_len);
+                    if expected_frame_id != ctx.ref_frame_id[ctx.ref_frame_idx[i] as usize]
+                        && ctx.mode == ParseMode::Strict
+                    {
+                        return Err(ObuError::FrameIdMismatch);
+                    }
                 }
             }
 
@@ -471,7 +1764,7 @@ impl UncompressedHeader {
                 render_size(ctx, buf);
             }
 
-            let allow_high_precision_mv = if force_integer_mv {
+            allow_high_precision_mv = if force_integer_mv {
                 false
             } else {
                 // allow_high_precision_mv	f(1)
@@ -489,16 +1782,14 @@ impl UncompressedHeader {
                 buf.get_bit()
             };
 
-            // for ( i = 0; i < REFS_PER_FRAME; i++ ) {
-            //     refFrame = LAST_FRAME + i
-            //     hint = RefOrderHint[ ref_frame_idx[ i ] ]
-            //     OrderHints[ refFrame ] = hint
-            //     if ( !enable_order_hint ) {
-            //         RefFrameSignBias[ refFrame ] = 0
-            //     } else {
-            //         RefFrameSignBias[ refFrame ] = get_relative_dist( hint, OrderHint) > 0
-            //     }
-            // }
+            for i in 0..REFS_PER_FRAME as usize {
+                let ref_frame = LAST_FRAME as usize + i;
+                let hint = ctx.ref_order_hint[ctx.ref_frame_idx[i] as usize];
+                ctx.order_hints[ref_frame] = hint;
+
+                // get_relative_dist() is always 0 without enable_order_hint.
+                ctx.ref_frame_sign_bias[ref_frame] = ctx.get_relative_dist(hint, order_hint) > 0;
+            }
         }
 
         let disable_frame_end_update_cdf =
@@ -521,22 +1812,247 @@ impl UncompressedHeader {
             // load_previous( )
         }
 
-        Ok(Self {})
+        // TODO:
+        //
+        // if ( use_ref_frame_mvs == 1 )
+        //     motion_field_estimation( )
+
+        let tile_info = TileInfo::decode(ctx, buf);
+        ctx.tile_info = tile_info.clone();
+
+        let quantization_params = QuantizationParams::decode(ctx, buf);
+
+        let segmentation_params = SegmentationParams::decode(ctx, buf, primary_ref_frame);
+        ctx.segmentation_params = segmentation_params;
+
+        let delta_q_params = DeltaQParams::decode(buf, quantization_params.base_q_idx);
+        let delta_lf_params =
+            DeltaLfParams::decode(buf, delta_q_params.delta_q_present, allow_intrabc);
+
+        let qp = &quantization_params;
+        let zero_deltas = qp.delta_q_y_dc == 0
+            && qp.delta_q_u_ac == 0
+            && qp.delta_q_u_dc == 0
+            && qp.delta_q_v_ac == 0
+            && qp.delta_q_v_dc == 0;
+
+        let coded_lossless = zero_deltas
+            && (0..MAX_SEGMENTS as usize)
+                .all(|segment_id| segmentation_params.qindex(qp.base_q_idx, segment_id) == 0);
+
+        let all_lossless = coded_lossless && ctx.frame_width == ctx.upscaled_width;
+        ctx.coded_lossless = coded_lossless;
+        ctx.all_lossless = all_lossless;
+
+        let loop_filter_params =
+            LoopFilterParams::decode(ctx, buf, primary_ref_frame, allow_intrabc);
+        ctx.loop_filter_params = loop_filter_params;
+
+        let cdef_params = CdefParams::decode(ctx, buf, allow_intrabc);
+        let lr_params = LrParams::decode(ctx, buf, allow_intrabc)?;
+        let tx_mode = read_tx_mode(ctx, buf);
+
+        let reference_select = if ctx.frame_is_intra {
+            false
+        } else {
+            // reference_select	f(1)
+            buf.get_bit()
+        };
+
+        let skip_mode_frame = match ctx.skip_mode_frames(reference_select) {
+            // skip_mode_present	f(1)
+            Some(frames) if buf.get_bit() => Some(frames),
+            _ => None,
+        };
+
+        let allow_warped_motion = if ctx.frame_is_intra
+            || error_resilient_mode
+            || !sequence_header.enable_warped_motion
+        {
+            false
+        } else {
+            // allow_warped_motion	f(1)
+            buf.get_bit()
+        };
+
+        // reduced_tx_set	f(1)
+        let reduced_tx_set = buf.get_bit();
+
+        let global_motion_params =
+            GlobalMotionParams::decode(ctx, buf, primary_ref_frame, allow_high_precision_mv);
+        ctx.global_motion_params = global_motion_params;
+
+        ctx.film_grain_params =
+            FilmGrainParams::decode(ctx, buf, frame_type, show_frame, showable_frame)?;
+
+        ctx.update_ref_frames(refresh_frame_flags, frame_type, showable_frame);
+
+        Ok(Self {
+            frame_to_show_map_idx: None,
+            frame_type,
+            show_frame,
+            temporal_point_info,
+            display_frame_id,
+            showable_frame,
+            error_resilient_mode,
+            primary_ref_frame,
+            disable_cdf_update,
+            disable_frame_end_update_cdf,
+            buffer_removal_times,
+            refresh_frame_flags,
+            allow_screen_content_tools,
+            force_integer_mv,
+            upscaled_width: ctx.upscaled_width,
+            frame_width: ctx.frame_width,
+            frame_height: ctx.frame_height,
+            render_and_frame_size_different: ctx.render_width != ctx.upscaled_width
+                || ctx.render_height != ctx.frame_height,
+            render_width: ctx.render_width,
+            render_height: ctx.render_height,
+            allow_intrabc,
+            use_ref_frame_mvs,
+            tile_info,
+            quantization_params,
+            segmentation_params,
+            delta_q_params,
+            delta_lf_params,
+            coded_lossless,
+            all_lossless,
+            loop_filter_params,
+            cdef_params,
+            lr_params,
+            tx_mode,
+            reference_select,
+            skip_mode_frame,
+            allow_warped_motion,
+            reduced_tx_set,
+            global_motion_params,
+            film_grain_params: ctx.film_grain_params.clone(),
+        })
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct FrameHeader {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FrameHeader {
+    pub uncompressed_header: UncompressedHeader,
+}
+
+impl std::fmt::Display for FrameHeader {
+    /// A one line summary, e.g. `KEY_FRAME 1920x1080 base_q_idx=32 tiles=4
+    /// show=1`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header = &self.uncompressed_header;
+        if let Some(idx) = header.frame_to_show_map_idx {
+            write!(f, "show_existing_frame={} ", idx)?;
+        }
+
+        write!(
+            f,
+            "{} {}x{}",
+            header.frame_type, header.upscaled_width, header.frame_height
+        )?;
+
+        if header.frame_to_show_map_idx.is_none() {
+            write!(
+                f,
+                " base_q_idx={} tiles={} show={}",
+                header.quantization_params.base_q_idx,
+                self.num_tiles(),
+                header.show_frame as u8
+            )?;
+        }
+
+        Ok(())
+    }
+}
 
 impl FrameHeader {
+    pub fn frame_type(&self) -> FrameType {
+        self.uncompressed_header.frame_type
+    }
+
+    pub fn show_frame(&self) -> bool {
+        self.uncompressed_header.show_frame
+    }
+
+    /// The presentation time of the frame in seconds from the last random
+    /// access point, `None` when the frame doesn't signal one.
+    pub fn presentation_time(&self, sequence_header: &SequenceHeader) -> Option<f64> {
+        self.uncompressed_header
+            .temporal_point_info
+            .as_ref()?
+            .presentation_time(sequence_header.timing_info.as_ref()?)
+    }
+
+    pub fn tile_info(&self) -> &TileInfo {
+        &self.uncompressed_header.tile_info
+    }
+
+    pub fn num_tiles(&self) -> u32 {
+        self.tile_info().num_tiles()
+    }
+
+    pub fn tile_cols(&self) -> u32 {
+        self.tile_info().tile_cols
+    }
+
+    pub fn tile_rows(&self) -> u32 {
+        self.tile_info().tile_rows
+    }
+
     pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
         if ctx.seen_frame_header {
-            // TODO:
-            // frame_header_copy
-        } else {
+            return Self::decode_copy(ctx, buf);
+        }
+
+        ctx.next_tile_num = 0;
+
+        let mut start = buf.clone();
+        let start_position = buf.get_position();
+
+        // The next frame header is decoded again when this one fails.
+        let header = Self {
+            uncompressed_header: UncompressedHeader::decode(ctx, buf)?,
+        };
+
+        // A shown existing frame has no tile group, the next frame header
+        // starts a new frame.
+        if header.uncompressed_header.frame_to_show_map_idx.is_none() {
             ctx.seen_frame_header = true;
+            ctx.frames_parsed += 1;
+
+            // The header starts at the beginning of the OBU payload, which is
+            // byte aligned.
+            let bits = buf.get_position() - start_position;
+            ctx.frame_header_bytes = start.read_bytes(bits.div_ceil(8)).to_vec();
+            ctx.frame_header_bits = bits;
+            ctx.frame_header = Some(header.clone());
+        }
+
+        Ok(header)
+    }
+
+    /// frame_header_copy(), the bits of the frame header of the current frame
+    /// repeated. In lenient mode the frame header of the current frame is
+    /// returned even when the copy differs.
+    fn decode_copy(ctx: &ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
+        let header = ctx
+            .frame_header
+            .clone()
+            .ok_or(ObuError::NotFoundFrameHeader)?;
+
+        let bits = ctx.frame_header_bits;
+        let identical = buf.remaining_bytes() * 8 >= bits
+            && (0..bits).all(|i| {
+                let bit = (ctx.frame_header_bytes[i / 8] >> (7 - i % 8)) & 1 == 1;
+                buf.get_bit() == bit
+            });
+
+        if !identical && ctx.mode == ParseMode::Strict {
+            return Err(ObuError::FrameHeaderMismatch);
         }
 
-        Ok(Self {})
+        Ok(header)
     }
 }
@@ -0,0 +1,80 @@
+/// An AV1 level, `major.minor`, coded in the bitstream as `seq_level_idx`.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Level {
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// Limits a stream must stay within to conform to a level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LevelLimits {
+    /// MaxPicSize, in luma samples.
+    pub max_pic_size: u32,
+    /// MaxHSize, in luma samples.
+    pub max_h_size: u32,
+    /// MaxVSize, in luma samples.
+    pub max_v_size: u32,
+    /// MaxDisplayRate, in luma samples per second.
+    pub max_display_rate: u64,
+    /// MaxDecodeRate, in luma samples per second.
+    pub max_decode_rate: u64,
+}
+
+impl Level {
+    /// seq_level_idx 31, a level without any limits.
+    pub const MAX_PARAMETERS: Self = Self::new(9, 3);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// seq_level_idx = ( major - 2 ) * 4 + minor
+    pub const fn from_seq_level_idx(seq_level_idx: u8) -> Self {
+        Self::new(2 + (seq_level_idx >> 2), seq_level_idx & 3)
+    }
+
+    pub const fn seq_level_idx(&self) -> u8 {
+        (self.major.saturating_sub(2) << 2) | (self.minor & 3)
+    }
+
+    /// The limits of the level, `None` for levels without any limits: the
+    /// levels not defined by the specification and `MAX_PARAMETERS`.
+    pub fn limits(&self) -> Option<LevelLimits> {
+        let (max_pic_size, max_h_size, max_v_size, max_display_rate, max_decode_rate) =
+            match self.seq_level_idx() {
+                0 => (147456, 2048, 1152, 4423680, 5529600),
+                1 => (278784, 2816, 1584, 8363520, 10454400),
+                4 => (665856, 4352, 2448, 19975680, 24969600),
+                5 => (1065024, 5504, 3096, 31950720, 39938400),
+                8 => (2359296, 6144, 3456, 70778880, 77856768),
+                9 => (2359296, 6144, 3456, 141557760, 155713536),
+                12 => (8912896, 8192, 4352, 267386880, 273715200),
+                13 => (8912896, 8192, 4352, 534773760, 547430400),
+                14 => (8912896, 8192, 4352, 1069547520, 1094860800),
+                15 => (8912896, 8192, 4352, 1069547520, 1176502272),
+                16 => (35651584, 16384, 8704, 1069547520, 1176502272),
+                17 => (35651584, 16384, 8704, 2139095040, 2189721600),
+                18 => (35651584, 16384, 8704, 4278190080, 4379443200),
+                19 => (35651584, 16384, 8704, 4278190080, 4706009088),
+                _ => return None,
+            };
+
+        Some(LevelLimits {
+            max_pic_size,
+            max_h_size,
+            max_v_size,
+            max_display_rate,
+            max_decode_rate,
+        })
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
@@ -1,38 +1,60 @@
-use super::{Buffer, ObuError, ObuUnknownError};
+use super::{
+    Buffer, ObuContext, ObuError, ObuHeader, ObuType, ObuUnknownError, ParseMode, read_leb128,
+};
 
+use crate::writer::Writer;
+
+/// see: https://aomediacodec.github.io/av1-spec/#metadata-obu-semantics
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MetadataType {
+    /// Reserved for AOM use, 0 and 32 or greater.
+    Reserved(u32),
     HdrCll,
     HdrMdcv,
     Scalability,
     ItutT35,
     Timecode,
-    UnregisteredUserPrivate,
+    /// Unregistered user private, 6 to 31.
+    Unregistered(u32),
 }
 
-impl TryFrom<u8> for MetadataType {
-    type Error = ObuError;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        Ok(match value {
+impl From<u32> for MetadataType {
+    fn from(value: u32) -> Self {
+        match value {
             1 => Self::HdrCll,
             2 => Self::HdrMdcv,
             3 => Self::Scalability,
             4 => Self::ItutT35,
             5 => Self::Timecode,
-            6..=31 => Self::UnregisteredUserPrivate,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::ChromaSamplePosition)),
-        })
+            6..=31 => Self::Unregistered(value),
+            _ => Self::Reserved(value),
+        }
+    }
+}
+
+impl From<MetadataType> for u32 {
+    fn from(value: MetadataType) -> Self {
+        match value {
+            MetadataType::Reserved(value) | MetadataType::Unregistered(value) => value,
+            MetadataType::HdrCll => 1,
+            MetadataType::HdrMdcv => 2,
+            MetadataType::Scalability => 3,
+            MetadataType::ItutT35 => 4,
+            MetadataType::Timecode => 5,
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SpatialLayer {
     pub max_width: u16,
     pub max_height: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TemporalGroup {
     pub temporal_id: u8,
     pub temporal_switching_up_point: bool,
@@ -43,7 +65,7 @@ pub struct TemporalGroup {
 impl TemporalGroup {
     pub fn decode(buf: &mut Buffer) -> Self {
         // temporal_group_temporal_id[ i ]	f(3)
-        let temporal_id = buf.get_bits(3) as u8;
+        let temporal_id = buf.get_bits_u8(3);
 
         // temporal_group_temporal_switching_up_point_flag[ i ]	f(1)
         let temporal_switching_up_point = buf.get_bit();
@@ -55,7 +77,7 @@ impl TemporalGroup {
         let ref_cnt = buf.get_bits(3) as usize;
         let mut ref_pic_diffs = Vec::with_capacity(ref_cnt);
         for _ in 0..ref_cnt {
-            ref_pic_diffs.push(buf.get_bits(8) as u8);
+            ref_pic_diffs.push(buf.get_bits_u8(8));
         }
 
         Self {
@@ -65,10 +87,32 @@ impl TemporalGroup {
             ref_pic_diffs,
         }
     }
+
+    pub fn encode(&self, writer: &mut Writer) {
+        // temporal_group_temporal_id[ i ]	f(3)
+        writer.put_bits(3, self.temporal_id as u32);
+
+        // temporal_group_temporal_switching_up_point_flag[ i ]	f(1)
+        writer.put_bit(self.temporal_switching_up_point);
+
+        // temporal_group_spatial_switching_up_point_flag[ i ]	f(1)
+        writer.put_bit(self.spatial_switching_up_point);
+
+        // temporal_group_ref_cnt[ i ]	f(3)
+        writer.put_bits(3, self.ref_pic_diffs.len() as u32);
+        for diff in &self.ref_pic_diffs {
+            // temporal_group_ref_pic_diff[ i ][ j ]	f(8)
+            writer.put_bits(8, *diff as u32);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ScalabilityStructure {
+    /// spatial_layers_cnt_minus_1 + 1, from 1 to 4. `spatial_layers` and
+    /// `spatial_layer_descriptions` have this many entries when present.
+    pub spatial_layers_cnt: u8,
     pub spatial_layers: Option<Vec<SpatialLayer>>,
     pub spatial_layer_descriptions: Option<Vec<u8>>,
     pub temporal_groups: Option<Vec<TemporalGroup>>,
@@ -77,7 +121,7 @@ pub struct ScalabilityStructure {
 impl ScalabilityStructure {
     pub fn decode(buf: &mut Buffer) -> Self {
         // spatial_layers_cnt_minus_1	f(2)
-        let spatial_layers_cnt = buf.get_bits(2) as usize + 1;
+        let spatial_layers_cnt = buf.get_bits_u8(2) + 1;
 
         // spatial_layer_dimensions_present_flag	f(1)
         let spatial_layer_dimensions_present = buf.get_bit();
@@ -92,13 +136,13 @@ impl ScalabilityStructure {
         buf.seek_bits(3);
 
         let spatial_layers = if spatial_layer_dimensions_present {
-            let mut spatial_layers = Vec::with_capacity(spatial_layers_cnt);
+            let mut spatial_layers = Vec::with_capacity(spatial_layers_cnt as usize);
             for _ in 0..spatial_layers_cnt {
                 spatial_layers.push(SpatialLayer {
                     // spatial_layer_max_width[ i ]	f(16)
-                    max_width: buf.get_bits(16) as u16,
+                    max_width: buf.get_bits_u16(16),
                     // spatial_layer_max_height[ i ]	f(16)
-                    max_height: buf.get_bits(16) as u16,
+                    max_height: buf.get_bits_u16(16),
                 })
             }
 
@@ -108,11 +152,11 @@ impl ScalabilityStructure {
         };
 
         let spatial_layer_descriptions = if spatial_layer_description_present {
-            let mut spatial_layer_ref_ids = Vec::with_capacity(spatial_layers_cnt);
+            let mut spatial_layer_ref_ids = Vec::with_capacity(spatial_layers_cnt as usize);
             for _ in 0..spatial_layers_cnt {
                 spatial_layer_ref_ids.push(
                     // spatial_layer_ref_id[ i ]	f(8)
-                    buf.get_bits(8) as u8,
+                    buf.get_bits_u8(8),
                 )
             }
 
@@ -135,14 +179,58 @@ impl ScalabilityStructure {
         };
 
         Self {
+            spatial_layers_cnt,
             spatial_layers,
             spatial_layer_descriptions,
             temporal_groups,
         }
     }
+
+    pub fn encode(&self, writer: &mut Writer) {
+        // spatial_layers_cnt_minus_1	f(2)
+        writer.put_bits(2, self.spatial_layers_cnt.saturating_sub(1) as u32);
+
+        // spatial_layer_dimensions_present_flag	f(1)
+        writer.put_bit(self.spatial_layers.is_some());
+
+        // spatial_layer_description_present_flag	f(1)
+        writer.put_bit(self.spatial_layer_descriptions.is_some());
+
+        // temporal_group_description_present_flag	f(1)
+        writer.put_bit(self.temporal_groups.is_some());
+
+        // scalability_structure_reserved_3bits	f(3)
+        writer.put_bits(3, 0);
+
+        if let Some(spatial_layers) = &self.spatial_layers {
+            for layer in spatial_layers {
+                // spatial_layer_max_width[ i ]	f(16)
+                writer.put_bits(16, layer.max_width as u32);
+
+                // spatial_layer_max_height[ i ]	f(16)
+                writer.put_bits(16, layer.max_height as u32);
+            }
+        }
+
+        if let Some(spatial_layer_descriptions) = &self.spatial_layer_descriptions {
+            for ref_id in spatial_layer_descriptions {
+                // spatial_layer_ref_id[ i ]	f(8)
+                writer.put_bits(8, *ref_id as u32);
+            }
+        }
+
+        if let Some(temporal_groups) = &self.temporal_groups {
+            // temporal_group_size	f(8)
+            writer.put_bits(8, temporal_groups.len() as u32);
+            for temporal_group in temporal_groups {
+                temporal_group.encode(writer);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ScalabilityModeIdc {
     L1T2,
     L1T3,
@@ -209,14 +297,17 @@ impl TryFrom<u8> for ScalabilityModeIdc {
             26 => Self::L3T3KeyShift,
             27 => Self::L4T5KeyShift,
             28 => Self::L4T7KeyShift,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::ScalabilityModeIdc)),
+            _ => return Err(ObuUnknownError::ScalabilityModeIdc.into()),
         })
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Metadata {
-    UnregisteredUserPrivate(u8),
+    /// Metadata of a reserved type, only kept in Lenient mode.
+    Reserved(u32),
+    UnregisteredUserPrivate(u32),
     HdrCll {
         max_cll: u16,
         max_fall: u16,
@@ -236,6 +327,8 @@ pub enum Metadata {
     ItutT35 {
         country_code: u8,
         country_code_extension_byte: Option<u8>,
+        /// itu_t_t35_payload_bytes, without the trailing bits.
+        payload: Vec<u8>,
     },
     Timecode {
         counting_type: u8,
@@ -252,32 +345,49 @@ pub enum Metadata {
 }
 
 impl Metadata {
-    pub fn decode(buf: &mut Buffer) -> Result<Self, ObuError> {
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer, size: usize) -> Result<Self, ObuError> {
+        let end_position = buf.get_position() + size * 8;
+
         // metadata_type	leb128()
-        let kind = buf.get_leb128() as u8;
-        Ok(match MetadataType::try_from(kind)? {
-            MetadataType::UnregisteredUserPrivate => Self::UnregisteredUserPrivate(kind),
+        Ok(match MetadataType::from(read_leb128(buf)?) {
+            MetadataType::Reserved(_) if ctx.mode == ParseMode::Strict => {
+                return Err(ObuUnknownError::MetadataType.into());
+            }
+            MetadataType::Reserved(kind) => Self::Reserved(kind),
+            MetadataType::Unregistered(kind) => Self::UnregisteredUserPrivate(kind),
             MetadataType::ItutT35 => {
                 // itu_t_t35_country_code	f(8)
-                let country_code = buf.get_bits(8) as u8;
+                let country_code = buf.get_bits_u8(8);
                 let country_code_extension_byte = if country_code == 0xFF {
                     // itu_t_t35_country_code_extension_byte	f(8)
-                    Some(buf.get_bits(8) as u8)
+                    Some(buf.get_bits_u8(8))
                 } else {
                     None
                 };
 
+                // The payload runs up to the trailing bits, the last non-zero
+                // byte of the OBU.
+                let remaining = end_position.saturating_sub(buf.get_position()) / 8;
+                let remaining = remaining.min(buf.remaining_bytes());
+                let len = buf
+                    .clone()
+                    .read_bytes(remaining)
+                    .iter()
+                    .rposition(|byte| *byte != 0)
+                    .unwrap_or(remaining);
+
                 Self::ItutT35 {
                     country_code,
                     country_code_extension_byte,
+                    payload: buf.read_bytes(len).to_vec(),
                 }
             }
             MetadataType::HdrCll => {
                 Self::HdrCll {
                     // max_cll	f(16)
-                    max_cll: buf.get_bits(16) as u16,
+                    max_cll: buf.get_bits_u16(16),
                     // max_fall	f(16)
-                    max_fall: buf.get_bits(16) as u16,
+                    max_fall: buf.get_bits_u16(16),
                 }
             }
             MetadataType::HdrMdcv => {
@@ -285,19 +395,19 @@ impl Metadata {
                 let mut primary_chromaticity_y = [0u16; 3];
                 for i in 0..3 {
                     // primary_chromaticity_x[ i ]	f(16)
-                    primary_chromaticity_x[i] = buf.get_bits(16) as u16;
+                    primary_chromaticity_x[i] = buf.get_bits_u16(16);
 
                     // primary_chromaticity_y[ i ]	f(16)
-                    primary_chromaticity_y[i] = buf.get_bits(16) as u16;
+                    primary_chromaticity_y[i] = buf.get_bits_u16(16);
                 }
 
                 Self::HdrMdcv {
                     primary_chromaticity_x,
                     primary_chromaticity_y,
                     // white_point_chromaticity_x	f(16)
-                    white_point_chromaticity_x: buf.get_bits(16) as u16,
+                    white_point_chromaticity_x: buf.get_bits_u16(16),
                     // white_point_chromaticity_y	f(16)
-                    white_point_chromaticity_y: buf.get_bits(16) as u16,
+                    white_point_chromaticity_y: buf.get_bits_u16(16),
                     // luminance_max	f(32)
                     luminance_max: buf.get_bits(32),
                     // luminance_min	f(32)
@@ -306,7 +416,7 @@ impl Metadata {
             }
             MetadataType::Scalability => {
                 // scalability_mode_idc	f(8)
-                let mode_idc = ScalabilityModeIdc::try_from(buf.get_bits(8) as u8)?;
+                let mode_idc = ScalabilityModeIdc::try_from(buf.get_bits_u8(8))?;
                 let scalability_structure = if mode_idc == ScalabilityModeIdc::SS {
                     Some(ScalabilityStructure::decode(buf))
                 } else {
@@ -320,7 +430,7 @@ impl Metadata {
             }
             MetadataType::Timecode => {
                 // counting_type	f(5)
-                let counting_type = buf.get_bits(5) as u8;
+                let counting_type = buf.get_bits_u8(5);
 
                 // full_timestamp_flag	f(1)
                 let full_timestamp = buf.get_bit();
@@ -332,35 +442,35 @@ impl Metadata {
                 let cnt_dropped = buf.get_bit();
 
                 // n_frames	f(9)
-                let n_frames = buf.get_bits(9) as u16;
+                let n_frames = buf.get_bits_u16(9);
 
                 let mut seconds_value = None;
                 let mut minutes_value = None;
                 let mut hours_value = None;
                 if full_timestamp {
                     // seconds_value	f(6)
-                    seconds_value = Some(buf.get_bits(6) as u8);
+                    seconds_value = Some(buf.get_bits_u8(6));
 
                     // minutes_value	f(6)
-                    minutes_value = Some(buf.get_bits(6) as u8);
+                    minutes_value = Some(buf.get_bits_u8(6));
 
                     // hours_value	f(5)
-                    hours_value = Some(buf.get_bits(6) as u8);
+                    hours_value = Some(buf.get_bits_u8(5));
                 } else {
                     // seconds_flag	f(1)
                     if buf.get_bit() {
                         // seconds_value	f(6)
-                        seconds_value = Some(buf.get_bits(6) as u8);
+                        seconds_value = Some(buf.get_bits_u8(6));
 
                         // minutes_flag	f(1)
                         if buf.get_bit() {
                             // minutes_value	f(6)
-                            minutes_value = Some(buf.get_bits(6) as u8);
+                            minutes_value = Some(buf.get_bits_u8(6));
 
                             // hours_flag	f(1)
                             if buf.get_bit() {
                                 // hours_value	f(5)
-                                hours_value = Some(buf.get_bits(6) as u8);
+                                hours_value = Some(buf.get_bits_u8(5));
                             }
                         }
                     }
@@ -390,3 +500,205 @@ impl Metadata {
         })
     }
 }
+
+impl Metadata {
+    /// Content light level information, both values are in candelas per
+    /// square meter.
+    pub fn hdr_cll(max_cll: u16, max_fall: u16) -> Self {
+        Self::HdrCll { max_cll, max_fall }
+    }
+
+    /// Mastering display color volume.
+    ///
+    /// `primaries` and `white_point` are (x, y) chromaticity coordinates in
+    /// 0.16 fixed-point format, the luminance values are in 24.8 and 18.14
+    /// fixed-point format respectively.
+    pub fn mastering_display(
+        primaries: [(u16, u16); 3],
+        white_point: (u16, u16),
+        luminance_max: u32,
+        luminance_min: u32,
+    ) -> Self {
+        Self::HdrMdcv {
+            primary_chromaticity_x: primaries.map(|(x, _)| x),
+            primary_chromaticity_y: primaries.map(|(_, y)| y),
+            white_point_chromaticity_x: white_point.0,
+            white_point_chromaticity_y: white_point.1,
+            luminance_max,
+            luminance_min,
+        }
+    }
+
+    pub fn kind(&self) -> MetadataType {
+        match self {
+            Self::Reserved(kind) => MetadataType::Reserved(*kind),
+            Self::UnregisteredUserPrivate(kind) => MetadataType::Unregistered(*kind),
+            Self::HdrCll { .. } => MetadataType::HdrCll,
+            Self::HdrMdcv { .. } => MetadataType::HdrMdcv,
+            Self::Scalability { .. } => MetadataType::Scalability,
+            Self::ItutT35 { .. } => MetadataType::ItutT35,
+            Self::Timecode { .. } => MetadataType::Timecode,
+        }
+    }
+
+    /// Writes the metadata OBU payload, without the OBU header and trailing
+    /// bits.
+    pub fn encode(&self, writer: &mut Writer) {
+        // metadata_type	leb128()
+        writer.put_leb128(self.kind().into());
+
+        match self {
+            Self::Reserved(_) | Self::UnregisteredUserPrivate(_) => (),
+            Self::ItutT35 {
+                country_code,
+                country_code_extension_byte,
+                payload,
+            } => {
+                // itu_t_t35_country_code	f(8)
+                writer.put_bits(8, *country_code as u32);
+
+                if let Some(byte) = country_code_extension_byte {
+                    // itu_t_t35_country_code_extension_byte	f(8)
+                    writer.put_bits(8, *byte as u32);
+                }
+
+                // itu_t_t35_payload_bytes
+                writer.put_bytes(payload);
+            }
+            Self::HdrCll { max_cll, max_fall } => {
+                // max_cll	f(16)
+                writer.put_bits(16, *max_cll as u32);
+
+                // max_fall	f(16)
+                writer.put_bits(16, *max_fall as u32);
+            }
+            Self::HdrMdcv {
+                primary_chromaticity_x,
+                primary_chromaticity_y,
+                white_point_chromaticity_x,
+                white_point_chromaticity_y,
+                luminance_max,
+                luminance_min,
+            } => {
+                for i in 0..3 {
+                    // primary_chromaticity_x[ i ]	f(16)
+                    writer.put_bits(16, primary_chromaticity_x[i] as u32);
+
+                    // primary_chromaticity_y[ i ]	f(16)
+                    writer.put_bits(16, primary_chromaticity_y[i] as u32);
+                }
+
+                // white_point_chromaticity_x	f(16)
+                writer.put_bits(16, *white_point_chromaticity_x as u32);
+
+                // white_point_chromaticity_y	f(16)
+                writer.put_bits(16, *white_point_chromaticity_y as u32);
+
+                // luminance_max	f(32)
+                writer.put_bits(32, *luminance_max);
+
+                // luminance_min	f(32)
+                writer.put_bits(32, *luminance_min);
+            }
+            Self::Scalability {
+                mode_idc,
+                scalability_structure,
+            } => {
+                // scalability_mode_idc	f(8)
+                writer.put_bits(8, *mode_idc as u32);
+
+                if let Some(scalability_structure) = scalability_structure {
+                    scalability_structure.encode(writer);
+                }
+            }
+            Self::Timecode {
+                counting_type,
+                full_timestamp,
+                discontinuity,
+                cnt_dropped,
+                n_frames,
+                seconds_value,
+                minutes_value,
+                hours_value,
+                time_offset_length,
+                time_offset_value,
+            } => {
+                // counting_type	f(5)
+                writer.put_bits(5, *counting_type as u32);
+
+                // full_timestamp_flag	f(1)
+                writer.put_bit(*full_timestamp);
+
+                // discontinuity_flag	f(1)
+                writer.put_bit(*discontinuity);
+
+                // cnt_dropped_flag	f(1)
+                writer.put_bit(*cnt_dropped);
+
+                // n_frames	f(9)
+                writer.put_bits(9, *n_frames as u32);
+
+                if *full_timestamp {
+                    // seconds_value	f(6)
+                    writer.put_bits(6, seconds_value.unwrap_or(0) as u32);
+
+                    // minutes_value	f(6)
+                    writer.put_bits(6, minutes_value.unwrap_or(0) as u32);
+
+                    // hours_value	f(5)
+                    writer.put_bits(5, hours_value.unwrap_or(0) as u32);
+                } else {
+                    // seconds_flag	f(1)
+                    writer.put_bit(seconds_value.is_some());
+                    if let Some(seconds_value) = seconds_value {
+                        // seconds_value	f(6)
+                        writer.put_bits(6, *seconds_value as u32);
+
+                        // minutes_flag	f(1)
+                        writer.put_bit(minutes_value.is_some());
+                        if let Some(minutes_value) = minutes_value {
+                            // minutes_value	f(6)
+                            writer.put_bits(6, *minutes_value as u32);
+
+                            // hours_flag	f(1)
+                            writer.put_bit(hours_value.is_some());
+                            if let Some(hours_value) = hours_value {
+                                // hours_value	f(5)
+                                writer.put_bits(5, *hours_value as u32);
+                            }
+                        }
+                    }
+                }
+
+                // time_offset_length	f(5)
+                writer.put_bits(5, *time_offset_length as u32);
+                if *time_offset_length > 0 {
+                    // time_offset_value	f(n)
+                    writer.put_bits(*time_offset_length, time_offset_value.unwrap_or(0));
+                }
+            }
+        }
+    }
+
+    /// Serializes the metadata into a complete metadata OBU, including the
+    /// OBU header, `obu_size` and trailing bits.
+    pub fn to_obu(&self) -> Vec<u8> {
+        let mut payload = Writer::new();
+        self.encode(&mut payload);
+        payload.trailing_bits();
+
+        let payload = payload.into_bytes();
+        let mut writer = Writer::new();
+        ObuHeader {
+            r#type: ObuType::Metadata,
+            has_size: true,
+            extension: None,
+        }
+        .encode(&mut writer);
+
+        // obu_size	leb128()
+        writer.put_leb128(payload.len() as u32);
+        writer.put_bytes(&payload);
+        writer.into_bytes()
+    }
+}
@@ -1,18 +1,36 @@
+pub mod annexb;
 pub mod frame;
 pub mod frame_header;
+pub mod level;
 pub mod metadata;
 pub mod sequence_header;
+pub mod stats;
+pub mod streaming;
 pub mod tile_group;
 pub mod tile_list;
 
 use frame::Frame;
-use frame_header::{FrameHeader, FrameType};
+use frame_header::{
+    FilmGrainParams, FrameHeader, FrameType, GlobalMotionParams, LoopFilterParams,
+    SegmentationParams, TileInfo,
+};
+use metadata::Metadata;
 use sequence_header::SequenceHeader;
+use stats::ParseStats;
+use tile_group::TileGroup;
 
-use crate::buffer::Buffer;
+use crate::{
+    buffer::Buffer,
+    constants::{
+        ALTREF_FRAME, ALTREF2_FRAME, BWDREF_FRAME, GOLDEN_FRAME, LAST_FRAME, LAST2_FRAME,
+        LAST3_FRAME, NONE, NUM_REF_FRAMES, REFS_PER_FRAME, TOTAL_REFS_PER_FRAME,
+    },
+    writer::Writer,
+};
 
 /// see: https://aomediacodec.github.io/av1-spec/#obu-header-semantics
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ObuType {
     Reserved(u8),
     SequenceHeader,
@@ -52,38 +70,71 @@ impl TryFrom<u8> for ObuType {
             7 => Self::RedundantFrameHeader,
             8 => Self::TileList,
             15 => Self::Padding,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::ObuHeaderType)),
+            _ => return Err(ObuUnknownError::ObuHeaderType.into()),
         })
     }
 }
 
+impl From<ObuType> for u8 {
+    fn from(value: ObuType) -> Self {
+        match value {
+            ObuType::Reserved(value) => value,
+            ObuType::SequenceHeader => 1,
+            ObuType::TemporalDelimiter => 2,
+            ObuType::FrameHeader => 3,
+            ObuType::TileGroup => 4,
+            ObuType::Metadata => 5,
+            ObuType::Frame => 6,
+            ObuType::RedundantFrameHeader => 7,
+            ObuType::TileList => 8,
+            ObuType::Padding => 15,
+        }
+    }
+}
+
 /// https://aomediacodec.github.io/av1-spec/#obu-extension-header-syntax
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObuHeaderExtension {
     pub temporal_id: u8,
     pub spatial_id: u8,
+    /// Must be 0, checked against the parse mode by `ObuParser`.
+    pub extension_header_reserved_3bits: u8,
 }
 
 impl ObuHeaderExtension {
     pub fn decode(buf: &mut Buffer<'_>) -> Result<Self, ObuError> {
         // temporal_id f(3)
-        let temporal_id = buf.get_bits(3) as u8;
+        let temporal_id = buf.get_bits_u8(3);
 
         // spatial_id f(2)
-        let spatial_id = buf.get_bits(2) as u8;
+        let spatial_id = buf.get_bits_u8(2);
 
-        // extension_header_reserved_3bits
-        buf.seek_bits(3);
+        // extension_header_reserved_3bits f(3)
+        let extension_header_reserved_3bits = buf.get_bits_u8(3);
 
         Ok(Self {
             temporal_id,
             spatial_id,
+            extension_header_reserved_3bits,
         })
     }
+
+    pub fn encode(&self, writer: &mut Writer) {
+        // temporal_id f(3)
+        writer.put_bits(3, self.temporal_id as u32);
+
+        // spatial_id f(2)
+        writer.put_bits(2, self.spatial_id as u32);
+
+        // extension_header_reserved_3bits
+        writer.put_bits(3, 0);
+    }
 }
 
 /// see: https://aomediacodec.github.io/av1-spec/#obu-header-syntax
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObuHeader {
     pub r#type: ObuType,
     pub has_size: bool,
@@ -96,7 +147,7 @@ impl ObuHeader {
         buf.seek_bits(1);
 
         // obu_type f(4)
-        let r#type = ObuType::try_from(buf.get_bits(4) as u8)?;
+        let r#type = ObuType::try_from(buf.get_bits_u8(4))?;
 
         // obu_extension_flag f(1)
         let obu_extension_flag = buf.get_bit();
@@ -108,6 +159,10 @@ impl ObuHeader {
         buf.seek_bits(1);
 
         let extension = if obu_extension_flag {
+            if buf.remaining_bytes() == 0 {
+                return Err(ObuError::UnexpectedEof);
+            }
+
             Some(ObuHeaderExtension::decode(buf.as_mut())?)
         } else {
             None
@@ -119,60 +174,587 @@ impl ObuHeader {
             extension,
         })
     }
+
+    pub fn encode(&self, writer: &mut Writer) {
+        // obu_forbidden_bit f(1)
+        writer.put_bit(false);
+
+        // obu_type f(4)
+        writer.put_bits(4, u8::from(self.r#type) as u32);
+
+        // obu_extension_flag f(1)
+        writer.put_bit(self.extension.is_some());
+
+        // obu_has_size_field f(1)
+        writer.put_bit(self.has_size);
+
+        // obu_reserved_1bit
+        writer.put_bit(false);
+
+        if let Some(extension) = &self.extension {
+            extension.encode(writer);
+        }
+    }
+}
+
+/// Decodes an OBU header and its `obu_size`, when present, leaving `buf` at
+/// the start of the payload. Enough to split a low overhead bitstream into
+/// OBUs without decoding them.
+pub fn parse_obu_header(buf: &mut Buffer) -> Result<(ObuHeader, Option<usize>), ObuError> {
+    let header = ObuHeader::decode(buf)?;
+    let size = if header.has_size {
+        // obu_size	leb128()
+        Some(read_leb128(buf)? as usize)
+    } else {
+        None
+    };
+
+    Ok((header, size))
+}
+
+/// `Buffer::get_leb128` for the sizes and values coded with leb128(), a value
+/// cut short by the end of the bitstream is `UnexpectedEof`.
+pub(crate) fn read_leb128(buf: &mut Buffer) -> Result<u32, ObuError> {
+    let position = buf.get_position();
+    match buf.get_leb128() {
+        Some(value) => Ok(value),
+        // Nothing is consumed when the bitstream ends within the leb128.
+        None if buf.get_position() == position => Err(ObuError::UnexpectedEof),
+        None => Err(ObuError::InvalidLeb128),
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Obu {
     SequenceHeader(SequenceHeader),
     Frame(Frame),
     FrameHeader(FrameHeader),
+    TileGroup(TileGroup),
+    Metadata(Metadata),
     TemporalDelimiter,
     Drop,
 }
 
+impl Obu {
+    pub fn as_sequence_header(&self) -> Option<&SequenceHeader> {
+        if let Self::SequenceHeader(it) = self {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_frame(&self) -> Option<&Frame> {
+        if let Self::Frame(it) = self {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_frame_header(&self) -> Option<&FrameHeader> {
+        if let Self::FrameHeader(it) = self {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_tile_group(&self) -> Option<&TileGroup> {
+        if let Self::TileGroup(it) = self {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_metadata(&self) -> Option<&Metadata> {
+        if let Self::Metadata(it) = self {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_temporal_delimiter(&self) -> bool {
+        matches!(self, Self::TemporalDelimiter)
+    }
+
+    pub fn is_drop(&self) -> bool {
+        matches!(self, Self::Drop)
+    }
+}
+
+/// How strictly bitstream conformance requirements are enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Violations are reported as errors.
+    #[default]
+    Strict,
+    /// Violations that don't prevent parsing are ignored.
+    Lenient,
+}
+
 #[derive(Default)]
 /// Open Bitstream Unit Parser
 ///
 /// see: https://aomediacodec.github.io/av1-spec/#obu-syntax
 pub struct ObuParser {
-    pub ctx: ObuContext,
+    ctx: ObuContext,
+    operating_point_idc: Option<u16>,
+    highest_operating_point: bool,
+    trailing_padding: bool,
+    truncated: bool,
+    stats: Option<ParseStats>,
 }
 
 impl ObuParser {
+    pub fn set_mode(&mut self, mode: ParseMode) {
+        self.ctx.mode = mode;
+    }
+
+    /// Enables or disables the collection of `ParseStats`, off by default.
+    /// Disabling it discards the totals collected so far.
+    pub fn collect_stats(&mut self, enabled: bool) {
+        self.stats = enabled.then(|| self.stats.take().unwrap_or_default());
+    }
+
+    /// The totals collected since `collect_stats` was enabled.
+    pub fn stats(&self) -> Option<&ParseStats> {
+        self.stats.as_ref()
+    }
+
+    /// Number of frames decoded so far, see `ObuContext::frames_parsed`.
+    pub fn frames_parsed(&self) -> u64 {
+        self.ctx.frames_parsed
+    }
+
+    /// The active sequence header, the last one parsed.
+    pub fn sequence_header(&self) -> Option<&SequenceHeader> {
+        self.ctx.sequence_header.as_ref()
+    }
+
+    /// The decoder state built from the OBUs parsed so far.
+    pub fn context(&self) -> &ObuContext {
+        &self.ctx
+    }
+
+    /// Whether the trailing bits of the last parsed OBU were followed by zero
+    /// padding bytes.
+    pub fn has_trailing_padding(&self) -> bool {
+        self.trailing_padding
+    }
+
+    /// Whether the last parsed OBU was cut short by the end of the buffer, its
+    /// obu_size was clamped to the bytes left (lenient mode only).
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Overrides the layer mask signaled by the selected operating point, OBUs
+    /// of the temporal/spatial layers not set in `idc` are dropped. Bit `i`
+    /// selects temporal layer `i` and bit `8 + j` selects spatial layer `j`,
+    /// `0` keeps every layer.
+    pub fn set_operating_point_idc(&mut self, idc: u16) {
+        self.operating_point_idc = Some(idc);
+    }
+
+    /// Selects the operating point containing the most layers instead of
+    /// operating point 0, so that no layer is dropped. Applies to the active
+    /// sequence header and to every following one.
+    pub fn select_highest_operating_point(&mut self) {
+        self.highest_operating_point = true;
+
+        if let Some(sequence_header) = self.ctx.sequence_header.clone() {
+            self.choose_operating_point(&sequence_header);
+        }
+    }
+
+    fn choose_operating_point(&mut self, sequence_header: &SequenceHeader) {
+        let operating_point = sequence_header.highest_operating_point();
+        self.ctx.operating_point = operating_point;
+        self.ctx.operating_point_idc = sequence_header.operating_points[operating_point].idc;
+    }
+
+    /// Decodes the type of the next OBU without consuming anything from
+    /// `buf`, this is enough to find temporal unit boundaries cheaply.
+    pub fn peek_obu_type(buf: &Buffer) -> Result<ObuType, ObuError> {
+        Ok(ObuHeader::decode(&mut buf.clone())?.r#type)
+    }
+
+    /// Skips forward to the next temporal delimiter or sequence header OBU,
+    /// where parsing can resume after a corrupt OBU. Returns false if the end
+    /// of the buffer is reached without finding one.
+    ///
+    /// Note: Only the low overhead bitstream format is supported, the OBU to
+    /// resume at must have `obu_has_size_field` set, with or without an
+    /// extension header.
+    pub fn resync(&mut self, buf: &mut Buffer) -> bool {
+        buf.byte_alignment();
+        while buf.remaining_bytes() > 0 {
+            if Self::is_resync_point(buf.clone()) {
+                return true;
+            }
+
+            buf.seek_bits(8);
+        }
+
+        false
+    }
+
+    fn is_resync_point(mut buf: Buffer) -> bool {
+        // The forbidden bit and the reserved bit are zero and the size field
+        // is present.
+        if buf.clone().get_bits(8) & 0x83 != 0x02 {
+            return false;
+        }
+
+        let Ok((header, Some(obu_size))) = parse_obu_header(&mut buf) else {
+            return false;
+        };
+
+        if header
+            .extension
+            .is_some_and(|it| it.extension_header_reserved_3bits != 0)
+        {
+            return false;
+        }
+
+        match header.r#type {
+            // A temporal delimiter has an empty payload.
+            ObuType::TemporalDelimiter => obu_size == 0,
+            // seq_profile	f(3)
+            ObuType::SequenceHeader => {
+                obu_size > 0 && obu_size <= buf.remaining_bytes() && buf.get_bits(3) <= 2
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns an iterator over the OBUs remaining in `buf`.
+    ///
+    /// The iteration ends when the buffer is exhausted or after the first
+    /// error.
+    pub fn iter<'a, 'b>(&'a mut self, buf: &'a mut Buffer<'b>) -> ObuIter<'a, 'b> {
+        ObuIter {
+            parser: self,
+            buf,
+            done: false,
+        }
+    }
+
     pub fn parse(&mut self, buf: &mut Buffer) -> Result<Obu, ObuError> {
-        let header = ObuHeader::decode(buf.as_mut())?;
-        let size = if header.has_size {
-            // obu_size leb128()
-            Some(buf.get_leb128() as usize)
+        self.parse_obu(buf, None)
+    }
+
+    /// Advances past the next `count` OBUs without decoding their payloads,
+    /// e.g. to seek. Sequence headers and temporal delimiters are still
+    /// parsed so that decoding can resume after them.
+    ///
+    /// Note: The skipped frame headers and tile groups don't update the
+    /// decoder state, parsing should resume at a temporal unit or a key frame.
+    pub fn skip(&mut self, buf: &mut Buffer, count: usize) -> Result<(), ObuError> {
+        for _ in 0..count {
+            if buf.remaining_bytes() == 0 {
+                return Err(ObuError::UnexpectedEof);
+            }
+
+            match Self::peek_obu_type(buf)? {
+                ObuType::SequenceHeader | ObuType::TemporalDelimiter => {
+                    self.parse(buf)?;
+                }
+                _ => {
+                    let (_, obu_size) = parse_obu_header(buf)?;
+
+                    // Without obu_size the OBU extends to the end of the buffer.
+                    let size = obu_size.unwrap_or(buf.remaining_bytes());
+                    if size > buf.remaining_bytes() && self.ctx.mode == ParseMode::Strict {
+                        return Err(ObuError::UnexpectedEof);
+                    }
+
+                    buf.seek_bits(size * 8);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses an OBU whose length in bytes, header included, is signaled
+    /// outside of the OBU, like `obu_length` in the Annex B format. Such OBUs
+    /// usually don't carry `obu_size`.
+    pub fn parse_with_length(
+        &mut self,
+        buf: &mut Buffer,
+        obu_length: usize,
+    ) -> Result<Obu, ObuError> {
+        let obu_start_position = buf.get_position();
+        let obu = self.parse_obu(buf, Some(obu_length))?;
+        buf.set_position(obu_start_position + obu_length * 8);
+
+        Ok(obu)
+    }
+
+    fn parse_obu(&mut self, buf: &mut Buffer, obu_length: Option<usize>) -> Result<Obu, ObuError> {
+        if buf.remaining_bytes() == 0 {
+            return Err(ObuError::UnexpectedEof);
+        }
+
+        let mut obu_start = buf.clone();
+        let obu_start_position = buf.get_position();
+        let (mut header, obu_size) = parse_obu_header(buf)?;
+        let mut size = if let Some(obu_size) = obu_size {
+            obu_size
+        } else if let Some(obu_length) = obu_length {
+            // obu_size = sz - 1 - obu_extension_flag
+            obu_length.saturating_sub((buf.get_position() - obu_start_position) / 8)
         } else {
-            None
+            // Without obu_size the OBU extends to the end of the buffer.
+            buf.remaining_bytes()
         };
 
-        if header.r#type != ObuType::SequenceHeader
+        // A payload cut short by the end of the buffer is parsed as far as it
+        // goes.
+        self.truncated = size > buf.remaining_bytes();
+        if self.truncated {
+            if self.ctx.mode == ParseMode::Strict {
+                return Err(ObuError::UnexpectedEof);
+            }
+
+            size = buf.remaining_bytes();
+        }
+
+        #[cfg(feature = "log")]
+        log::trace!(
+            "obu: type={:?}, size={}, temporal_id={:?}, spatial_id={:?}",
+            header.r#type,
+            size,
+            header.extension.map(|it| it.temporal_id),
+            header.extension.map(|it| it.spatial_id),
+        );
+
+        if let Some(extension) = &header.extension
+            && extension.extension_header_reserved_3bits != 0
+            && self.ctx.mode == ParseMode::Strict
+        {
+            buf.seek_bits(size * 8);
+            return Err(ObuError::InvalidReservedBits);
+        }
+
+        // The layer of an OBU must be one of the layers of the operating
+        // points, a stream without layers doesn't use the extension at all.
+        if let Some(extension) = header.extension.as_mut()
+            && header.r#type != ObuType::SequenceHeader
             && header.r#type != ObuType::TemporalDelimiter
-            && self.ctx.operating_point_idc != 0
+            && let Some(sequence_header) = &self.ctx.sequence_header
         {
-            if let Some(ext) = header.extension {
-                let in_temporal_layer = (1 >> ext.temporal_id) & 1;
-                let in_spatial_layer = (1 >> (ext.spatial_id + 8)) & 1;
-                if in_temporal_layer == 0 || in_spatial_layer == 0 {
-                    return Ok(Obu::Drop);
+            let (temporal_layers, spatial_layers) = sequence_header.max_layers();
+            let (max_temporal_id, max_spatial_id) = (temporal_layers - 1, spatial_layers - 1);
+
+            if extension.temporal_id > max_temporal_id || extension.spatial_id > max_spatial_id {
+                if self.ctx.mode == ParseMode::Strict {
+                    buf.seek_bits(size * 8);
+                    return Err(ObuError::InvalidLayerId);
                 }
+
+                extension.temporal_id = extension.temporal_id.min(max_temporal_id);
+                extension.spatial_id = extension.spatial_id.min(max_spatial_id);
+            }
+        }
+
+        self.ctx.obu_header_extension = header.extension;
+
+        let start_position = buf.get_position();
+        let end_position = start_position + size * 8;
+        let obu = match self.parse_payload(&header, buf, size) {
+            Ok(obu) => obu,
+            Err(e) => {
+                // An unsupported OBU is intact, it can be skipped.
+                if let ObuError::Unsupported(_) = e {
+                    buf.set_position(end_position);
+                }
+
+                // Values read past the end of the buffer are zeros, which
+                // might be the cause of the error.
+                #[cfg(feature = "checked-buffer")]
+                buf.check()?;
+
+                return Err(e);
+            }
+        };
+
+        #[cfg(feature = "checked-buffer")]
+        buf.check()?;
+
+        // Reading past obu_size means the OBU is corrupt (or the payload
+        // decoder is wrong), the next OBU still starts at obu_size.
+        if buf.get_position() > end_position {
+            if self.ctx.mode == ParseMode::Strict {
+                return Err(ObuError::ObuSizeOverrun);
+            }
+
+            buf.set_position(end_position);
+        }
+
+        // Only payloads that are parsed completely can be checked, otherwise
+        // the trailing bits can't be located.
+        let completely_parsed = match &obu {
+            Obu::SequenceHeader(_) => true,
+            Obu::Metadata(metadata) => {
+                !matches!(
+                    metadata,
+                    Metadata::Reserved(_) | Metadata::UnregisteredUserPrivate(_)
+                )
+            }
+            _ => false,
+        };
+
+        self.trailing_padding = false;
+        if size > 0 && completely_parsed {
+            let count = end_position.saturating_sub(buf.get_position());
+            if !(count > 0 && buf.trailing_bits(count)) && self.ctx.mode == ParseMode::Strict {
+                return Err(ObuError::InvalidTrailingBits);
+            }
+
+            self.trailing_padding = count > 8;
+        }
+
+        // Whatever the payload decoder left unread (trailing bits, padding or
+        // syntax that is not parsed yet) is skipped, so that the buffer always
+        // ends up at the start of the next OBU.
+        buf.set_position(end_position);
+
+        if let Obu::SequenceHeader(_) = &obu {
+            let length = (end_position - obu_start_position) / 8;
+            self.ctx.sequence_header_obu = obu_start
+                .read_bytes(length.min(obu_start.remaining_bytes()))
+                .to_vec();
+        }
+
+        if let Some(stats) = &mut self.stats {
+            stats.record(&obu, header.r#type, (buf.get_position() - obu_start_position) / 8);
+        }
+
+        Ok(obu)
+    }
+
+    fn parse_payload(
+        &mut self,
+        header: &ObuHeader,
+        buf: &mut Buffer,
+        size: usize,
+    ) -> Result<Obu, ObuError> {
+        let operating_point_idc = self
+            .operating_point_idc
+            .unwrap_or(self.ctx.operating_point_idc);
+
+        if header.r#type != ObuType::SequenceHeader
+            && header.r#type != ObuType::TemporalDelimiter
+            && operating_point_idc != 0
+            && let Some(ext) = header.extension
+        {
+            let in_temporal_layer = (operating_point_idc >> ext.temporal_id) & 1;
+            let in_spatial_layer = (operating_point_idc >> (ext.spatial_id + 8)) & 1;
+            if in_temporal_layer == 0 || in_spatial_layer == 0 {
+                #[cfg(feature = "log")]
+                log::warn!(
+                    "obu dropped, not in operating point: type={:?}, temporal_id={}, spatial_id={}",
+                    header.r#type,
+                    ext.temporal_id,
+                    ext.spatial_id,
+                );
+
+                return Ok(Obu::Drop);
             }
         }
 
         Ok(match header.r#type {
             ObuType::SequenceHeader => {
-                Obu::SequenceHeader(SequenceHeader::decode(&mut self.ctx, buf)?)
+                // A different sequence header starts a new coded video
+                // sequence, nothing derived from the previous sequence carries
+                // over. It is decoded in a fresh context which replaces the
+                // current one in that case.
+                let mut ctx = self.ctx.new_sequence();
+                let sequence_header = SequenceHeader::decode(&mut ctx, buf)?;
+                if self.ctx.sequence_header.as_ref() != Some(&sequence_header) {
+                    ctx.obu_header_extension = self.ctx.obu_header_extension;
+                    ctx.sequence_header = Some(sequence_header.clone());
+                    self.ctx = ctx;
+                }
+
+                if self.highest_operating_point {
+                    self.choose_operating_point(&sequence_header);
+                }
+
+                Obu::SequenceHeader(sequence_header)
             }
             ObuType::FrameHeader => Obu::FrameHeader(FrameHeader::decode(&mut self.ctx, buf)?),
-            ObuType::Frame => Obu::Frame(Frame::decode(&mut self.ctx, buf)?),
-            ObuType::TemporalDelimiter => Obu::TemporalDelimiter,
-            _ => todo!(),
+            ObuType::TileGroup => Obu::TileGroup(TileGroup::decode(&mut self.ctx, buf, size)?),
+            ObuType::Metadata => Obu::Metadata(Metadata::decode(&self.ctx, buf, size)?),
+            ObuType::Frame => Obu::Frame(Frame::decode(&mut self.ctx, buf, size)?),
+            ObuType::TemporalDelimiter => {
+                // A temporal unit always starts with a new frame header, even
+                // if the previous frame was cut short (e.g. when streams are
+                // concatenated).
+                self.ctx.seen_frame_header = false;
+                Obu::TemporalDelimiter
+            }
+            // Reserved OBUs and padding carry nothing for the decoder and are
+            // ignored.
+            ObuType::Reserved(_) | ObuType::Padding => {
+                #[cfg(feature = "log")]
+                if let ObuType::Reserved(value) = header.r#type {
+                    log::warn!("obu dropped, reserved type: {}", value);
+                }
+
+                Obu::Drop
+            }
+            // A redundant frame header repeats the frame header of the current
+            // frame, once the frame is complete there is nothing to check it
+            // against.
+            ObuType::RedundantFrameHeader => {
+                if self.ctx.seen_frame_header {
+                    Obu::FrameHeader(FrameHeader::decode(&mut self.ctx, buf)?)
+                } else {
+                    Obu::Drop
+                }
+            }
+            // Tile lists are only used by the large scale tile decoding
+            // process.
+            ObuType::TileList => {
+                return Err(ObuError::Unsupported(header.r#type));
+            }
         })
     }
 }
 
+/// Iterator over the OBUs of a buffer, created by `ObuParser::iter`.
+pub struct ObuIter<'a, 'b> {
+    parser: &'a mut ObuParser,
+    buf: &'a mut Buffer<'b>,
+    done: bool,
+}
+
+impl Iterator for ObuIter<'_, '_> {
+    type Item = Result<Obu, ObuError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.buf.remaining_bytes() == 0 {
+            return None;
+        }
+
+        let result = self.parser.parse(self.buf);
+        if result.is_err() {
+            self.done = true;
+        }
+
+        Some(result)
+    }
+}
+
+impl std::iter::FusedIterator for ObuIter<'_, '_> {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ObuUnknownError {
     ObuHeaderType,
@@ -186,45 +768,568 @@ pub enum ObuUnknownError {
     FrameType,
     InterpolationFilter,
     FrameTypeRefIndex,
+    FrameRestorationType,
+}
+
+impl std::fmt::Display for ObuUnknownError {
+    /// The syntax element holding the unknown value.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ObuHeaderType => "obu_type",
+            Self::Profile => "seq_profile",
+            Self::ColorPrimaries => "color_primaries",
+            Self::TransferCharacteristics => "transfer_characteristics",
+            Self::MatrixCoefficients => "matrix_coefficients",
+            Self::ChromaSamplePosition => "chroma_sample_position",
+            Self::MetadataType => "metadata_type",
+            Self::ScalabilityModeIdc => "scalability_mode_idc",
+            Self::FrameType => "frame_type",
+            Self::InterpolationFilter => "interpolation_filter",
+            Self::FrameTypeRefIndex => "frame_to_show_map_idx",
+            Self::FrameRestorationType => "lr_type",
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ObuError {
     Unknown(ObuUnknownError),
     NotFoundSequenceHeader,
+    NotFoundFrameHeader,
+    /// The OBU does not carry `obu_size`, so its end cannot be located.
+    MissingObuSize,
+    /// The payload is not followed by a stop bit and zero bits.
+    InvalidTrailingBits,
+    /// The tile range of a tile group is outside of the frame tiles.
+    InvalidTileRange,
+    /// A copy of the frame header differs from the frame header of the
+    /// current frame.
+    FrameHeaderMismatch,
+    /// show_existing_frame refers to a frame that cannot be shown (again).
+    FrameNotShowable,
+    /// The frame id of a reference frame is not the one signaled by the frame
+    /// header.
+    FrameIdMismatch,
+    /// film_grain_params_ref_idx is not one of the reference frames of the
+    /// frame.
+    FilmGrainParamsRefMismatch,
+    /// A leb128() value doesn't fit in 32 bits or is coded on more than 8
+    /// bytes.
+    InvalidLeb128,
+    /// The temporal_id or spatial_id of an OBU is not one of the layers of
+    /// the operating points.
+    InvalidLayerId,
+    /// extension_header_reserved_3bits is not 0.
+    InvalidReservedBits,
+    /// The payload decoder read past the end of the OBU.
+    ObuSizeOverrun,
+    /// A frame_unit_size or obu_length of the length delimited format
+    /// extends past the end of the enclosing unit.
+    InvalidObuLength,
+    /// The bitstream ends in the middle of a unit.
+    UnexpectedEof,
+    /// The OBU is valid but this parser can't decode it.
+    Unsupported(ObuType),
 }
 
 impl std::error::Error for ObuError {}
 
+#[cfg(feature = "checked-buffer")]
+impl From<crate::buffer::OutOfBounds> for ObuError {
+    fn from(_: crate::buffer::OutOfBounds) -> Self {
+        Self::UnexpectedEof
+    }
+}
+
 impl std::fmt::Display for ObuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            Self::Unknown(element) => write!(f, "invalid value of {}", element),
+            Self::NotFoundSequenceHeader => f.write_str("no sequence header"),
+            Self::NotFoundFrameHeader => f.write_str("no frame header"),
+            Self::MissingObuSize => f.write_str("obu_size is missing"),
+            Self::InvalidTrailingBits => f.write_str("invalid trailing bits"),
+            Self::InvalidTileRange => f.write_str("invalid tile range"),
+            Self::FrameHeaderMismatch => f.write_str("frame header copy mismatch"),
+            Self::FrameNotShowable => f.write_str("frame is not showable"),
+            Self::FrameIdMismatch => f.write_str("reference frame id mismatch"),
+            Self::FilmGrainParamsRefMismatch => {
+                f.write_str("film_grain_params_ref_idx is not a reference frame")
+            }
+            Self::InvalidLeb128 => f.write_str("invalid leb128"),
+            Self::InvalidLayerId => f.write_str("layer is not in the operating points"),
+            Self::InvalidReservedBits => f.write_str("reserved bits are not 0"),
+            Self::ObuSizeOverrun => f.write_str("payload overruns obu_size"),
+            Self::InvalidObuLength => f.write_str("unit overruns the enclosing unit"),
+            Self::UnexpectedEof => f.write_str("unexpected end of the bitstream"),
+            Self::Unsupported(r#type) => write!(f, "unsupported obu type {:?}", r#type),
+        }
+    }
+}
+
+impl From<ObuUnknownError> for ObuError {
+    fn from(element: ObuUnknownError) -> Self {
+        Self::Unknown(element)
     }
 }
 
-#[derive(Default, Debug)]
+/// Decoder state carried from one OBU to the next.
+///
+/// Cloning copies the full state (the active sequence header, the reference
+/// slots, ...), so a clone can be used to parse speculatively and be thrown
+/// away or kept.
+#[derive(Default, Debug, Clone)]
 pub struct ObuContext {
-    pub sequence_header: Option<SequenceHeader>,
-    pub obu_header_extension: Option<ObuHeaderExtension>,
-    pub num_planes: u8,
-    pub seen_frame_header: bool,
-    pub frame_is_intra: bool,
-    pub order_hint: u32,
-    pub frame_width: u16,
-    pub frame_height: u16,
-    pub superres_denom: u8,
-    pub upscaled_width: u16,
-    pub mi_cols: u32,
-    pub mi_rows: u32,
-    pub render_width: u16,
-    pub render_height: u16,
-    pub delta_frame_id: u32,
-    pub bit_depth: u8,
-    pub order_hint_bits: usize,
-    pub operating_point: usize,
-    pub operating_point_idc: u16,
-    pub ref_frame_type: Vec<FrameType>,
-    pub ref_frame_marking: Vec<bool>,
-    pub ref_order_hint: Vec<u32>,
-    pub ref_order_hints: Vec<bool>, 
+    pub(crate) mode: ParseMode,
+    pub(crate) sequence_header: Option<SequenceHeader>,
+    /// The active sequence header OBU, header included.
+    pub(crate) sequence_header_obu: Vec<u8>,
+    pub(crate) obu_header_extension: Option<ObuHeaderExtension>,
+    pub(crate) num_planes: u8,
+    pub(crate) seen_frame_header: bool,
+    /// The first tile expected in the next tile group of the current frame.
+    pub(crate) next_tile_num: u32,
+    /// The frame header of the current frame and its coded bits, the later
+    /// copies of the frame header must be identical.
+    pub(crate) frame_header: Option<FrameHeader>,
+    pub(crate) frame_header_bytes: Vec<u8>,
+    pub(crate) frame_header_bits: usize,
+    /// Number of frame headers decoded, copies of a frame header and
+    /// show_existing_frame headers are not counted.
+    pub(crate) frames_parsed: u64,
+    pub(crate) frame_is_intra: bool,
+    pub(crate) order_hint: u32,
+    pub(crate) frame_width: u32,
+    pub(crate) frame_height: u32,
+    pub(crate) superres_denom: u8,
+    pub(crate) upscaled_width: u32,
+    pub(crate) mi_cols: u32,
+    pub(crate) mi_rows: u32,
+    pub(crate) render_width: u32,
+    pub(crate) render_height: u32,
+    pub(crate) delta_frame_id: u32,
+    pub(crate) current_frame_id: u32,
+    /// RefFrameId
+    pub(crate) ref_frame_id: [u32; NUM_REF_FRAMES as usize],
+    pub(crate) bit_depth: u8,
+    pub(crate) order_hint_bits: usize,
+    pub(crate) operating_point: usize,
+    pub(crate) operating_point_idc: u16,
+    /// RefFrameType, `None` for the slots that were never refreshed.
+    pub(crate) ref_frame_type: [Option<FrameType>; NUM_REF_FRAMES as usize],
+    /// RefShowableFrame
+    pub(crate) ref_showable_frame: [bool; NUM_REF_FRAMES as usize],
+    pub(crate) ref_segmentation_params: [SegmentationParams; NUM_REF_FRAMES as usize],
+    pub(crate) ref_loop_filter_params: [LoopFilterParams; NUM_REF_FRAMES as usize],
+    /// SavedGmParams
+    pub(crate) ref_global_motion_params: [GlobalMotionParams; NUM_REF_FRAMES as usize],
+    pub(crate) ref_film_grain_params: [FilmGrainParams; NUM_REF_FRAMES as usize],
+    /// RefValid, cleared by shown key frames and by the slots whose order
+    /// hint or frame id don't match the ones signaled.
+    pub(crate) ref_frame_marking: [bool; NUM_REF_FRAMES as usize],
+    pub(crate) ref_order_hint: [u32; NUM_REF_FRAMES as usize],
+    /// RefUpscaledWidth
+    pub(crate) ref_upscaled_width: [u32; NUM_REF_FRAMES as usize],
+    /// RefFrameWidth
+    pub(crate) ref_frame_width: [u32; NUM_REF_FRAMES as usize],
+    /// RefFrameHeight
+    pub(crate) ref_frame_height: [u32; NUM_REF_FRAMES as usize],
+    /// RefRenderWidth
+    pub(crate) ref_render_width: [u32; NUM_REF_FRAMES as usize],
+    /// RefRenderHeight
+    pub(crate) ref_render_height: [u32; NUM_REF_FRAMES as usize],
+    /// OrderHints, the order hint of each reference frame type of the current
+    /// frame, indexed by `LAST_FRAME..=ALTREF_FRAME`.
+    pub(crate) order_hints: [u32; TOTAL_REFS_PER_FRAME as usize],
+    /// RefFrameSignBias, whether each reference frame type of the current
+    /// frame comes after it in display order.
+    pub(crate) ref_frame_sign_bias: [bool; TOTAL_REFS_PER_FRAME as usize],
+    pub(crate) ref_frame_idx: [u8; REFS_PER_FRAME as usize],
+    pub(crate) tile_info: TileInfo,
+    pub(crate) segmentation_params: SegmentationParams,
+    pub(crate) loop_filter_params: LoopFilterParams,
+    pub(crate) global_motion_params: GlobalMotionParams,
+    /// The film grain parameters of the current frame.
+    pub(crate) film_grain_params: FilmGrainParams,
+    pub(crate) coded_lossless: bool,
+    pub(crate) all_lossless: bool,
+}
+
+impl ObuContext {
+    /// The active sequence header OBU as it was coded, header and size field
+    /// included, e.g. for the `configOBUs` of an `av1C` box. `None` before the
+    /// first sequence header.
+    pub fn sequence_header_bytes(&self) -> Option<&[u8]> {
+        if self.sequence_header.is_some() {
+            Some(&self.sequence_header_obu)
+        } else {
+            None
+        }
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// The active sequence header, the last one parsed.
+    pub fn sequence_header(&self) -> Option<&SequenceHeader> {
+        self.sequence_header.as_ref()
+    }
+
+    /// The extension of the last OBU header, `None` if it had none.
+    pub fn obu_header_extension(&self) -> Option<&ObuHeaderExtension> {
+        self.obu_header_extension.as_ref()
+    }
+
+    /// The selected operating point and its operating_point_idc.
+    pub fn operating_point(&self) -> (usize, u16) {
+        (self.operating_point, self.operating_point_idc)
+    }
+
+    /// BitDepth of the active sequence header.
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    /// NumPlanes of the active sequence header, 1 for monochrome streams.
+    pub fn num_planes(&self) -> u8 {
+        self.num_planes
+    }
+
+    /// The frame header of the current frame, `None` before the first frame
+    /// of the coded video sequence.
+    pub fn frame_header(&self) -> Option<&FrameHeader> {
+        self.frame_header.as_ref()
+    }
+
+    /// Number of frame headers decoded, copies of a frame header and
+    /// show_existing_frame headers are not counted.
+    pub fn frames_parsed(&self) -> u64 {
+        self.frames_parsed
+    }
+
+    /// Whether the current frame is an intra frame.
+    pub fn frame_is_intra(&self) -> bool {
+        self.frame_is_intra
+    }
+
+    /// OrderHint of the current frame.
+    pub fn order_hint(&self) -> u32 {
+        self.order_hint
+    }
+
+    /// The coded size of the current frame (FrameWidth, FrameHeight), before
+    /// superres upscaling.
+    pub fn frame_dimensions(&self) -> (u32, u32) {
+        (self.frame_width, self.frame_height)
+    }
+
+    /// The size of the current frame after superres upscaling, this is the
+    /// size of the decoded picture.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.upscaled_width, self.frame_height)
+    }
+
+    /// The render size of the current frame (RenderWidth, RenderHeight).
+    pub fn render_dimensions(&self) -> (u32, u32) {
+        (self.render_width, self.render_height)
+    }
+
+    /// SuperresDenom of the current frame.
+    pub fn superres_denom(&self) -> u8 {
+        self.superres_denom
+    }
+
+    /// The size of the current frame in 4x4 blocks (MiCols, MiRows).
+    pub fn mi_dimensions(&self) -> (u32, u32) {
+        (self.mi_cols, self.mi_rows)
+    }
+
+    /// current_frame_id, 0 when frame ids are not present.
+    pub fn current_frame_id(&self) -> u32 {
+        self.current_frame_id
+    }
+
+    pub fn tile_info(&self) -> &TileInfo {
+        &self.tile_info
+    }
+
+    pub fn segmentation_params(&self) -> &SegmentationParams {
+        &self.segmentation_params
+    }
+
+    /// The film grain parameters of the current frame.
+    pub fn film_grain_params(&self) -> &FilmGrainParams {
+        &self.film_grain_params
+    }
+
+    /// CodedLossless and AllLossless of the current frame.
+    pub fn lossless(&self) -> (bool, bool) {
+        (self.coded_lossless, self.all_lossless)
+    }
+
+    /// RefFrameType of reference slot `i`, `None` if the slot was never
+    /// refreshed.
+    pub fn ref_frame_type(&self, i: usize) -> Option<FrameType> {
+        self.ref_frame_type[i]
+    }
+
+    /// RefOrderHint of reference slot `i`.
+    pub fn ref_order_hint(&self, i: usize) -> u32 {
+        self.ref_order_hint[i]
+    }
+
+    /// RefValid of reference slot `i`, whether it holds a frame that can be
+    /// referenced.
+    pub fn ref_valid(&self, i: usize) -> bool {
+        self.ref_frame_marking[i]
+    }
+
+    /// OrderHints, the order hint of each reference frame type of the
+    /// current frame, indexed by `LAST_FRAME..=ALTREF_FRAME`. Index
+    /// `INTRA_FRAME` is unused.
+    pub fn order_hints(&self) -> [u32; TOTAL_REFS_PER_FRAME as usize] {
+        self.order_hints
+    }
+
+    /// The upscaled size of the frame in reference slot `i` (RefUpscaledWidth,
+    /// RefFrameHeight).
+    pub fn ref_dimensions(&self, i: usize) -> (u32, u32) {
+        (self.ref_upscaled_width[i], self.ref_frame_height[i])
+    }
+
+    /// An empty context for a new coded video sequence, only the parsing
+    /// configuration is kept.
+    fn new_sequence(&self) -> Self {
+        Self {
+            mode: self.mode,
+            operating_point: self.operating_point,
+            ..Default::default()
+        }
+    }
+
+    /// The signed distance between two order hints, taking the wraparound of
+    /// the order hint modulus into account. Always 0 when order hints are
+    /// disabled.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-relative-distance
+    pub fn get_relative_dist(&self, a: u32, b: u32) -> i32 {
+        let enable_order_hint = self
+            .sequence_header
+            .as_ref()
+            .map(|v| v.enable_order_hint)
+            .unwrap_or(false);
+
+        if !enable_order_hint || self.order_hint_bits == 0 {
+            return 0;
+        }
+
+        let diff = a as i32 - b as i32;
+        let m = 1 << (self.order_hint_bits - 1);
+        (diff & (m - 1)) - (diff & m)
+    }
+
+    /// The two reference frames used by skip mode, `None` when skip mode is
+    /// not allowed for the current frame. `reference_select` is the value
+    /// from frame_reference_mode().
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#skip-mode-params-syntax
+    pub fn skip_mode_frames(&self, reference_select: bool) -> Option<[u8; 2]> {
+        let enable_order_hint = self
+            .sequence_header
+            .as_ref()
+            .map(|v| v.enable_order_hint)
+            .unwrap_or(false);
+
+        if self.frame_is_intra || !reference_select || !enable_order_hint {
+            return None;
+        }
+
+        let ref_hint = |i: usize| self.ref_order_hint[self.ref_frame_idx[i] as usize];
+
+        let mut forward: Option<(usize, u32)> = None;
+        let mut backward: Option<(usize, u32)> = None;
+        for i in 0..REFS_PER_FRAME as usize {
+            let hint = ref_hint(i);
+            let dist = self.get_relative_dist(hint, self.order_hint);
+            if dist < 0 {
+                if forward.is_none_or(|(_, v)| self.get_relative_dist(hint, v) > 0) {
+                    forward = Some((i, hint));
+                }
+            } else if dist > 0
+                && backward.is_none_or(|(_, v)| self.get_relative_dist(hint, v) < 0)
+            {
+                backward = Some((i, hint));
+            }
+        }
+
+        let (forward_idx, forward_hint) = forward?;
+        let other_idx = if let Some((backward_idx, _)) = backward {
+            backward_idx
+        } else {
+            let mut second_forward: Option<(usize, u32)> = None;
+            for i in 0..REFS_PER_FRAME as usize {
+                let hint = ref_hint(i);
+                if self.get_relative_dist(hint, forward_hint) < 0
+                    && second_forward.is_none_or(|(_, v)| self.get_relative_dist(hint, v) > 0)
+                {
+                    second_forward = Some((i, hint));
+                }
+            }
+
+            second_forward?.0
+        };
+
+        Some([
+            LAST_FRAME + forward_idx.min(other_idx) as u8,
+            LAST_FRAME + forward_idx.max(other_idx) as u8,
+        ])
+    }
+
+    /// Invalidates the reference frames whose id is too far from the id of
+    /// the current frame to be referenced.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#reference-frame-marking-process
+    pub(crate) fn mark_ref_frames(
+        &mut self,
+        current_frame_id: u32,
+        id_len: usize,
+        diff_len: usize,
+    ) {
+        for i in 0..NUM_REF_FRAMES as usize {
+            let ref_frame_id = self.ref_frame_id[i];
+            let invalid = if current_frame_id > (1 << diff_len) {
+                ref_frame_id > current_frame_id
+                    || ref_frame_id < current_frame_id - (1 << diff_len)
+            } else {
+                ref_frame_id > current_frame_id
+                    && ref_frame_id < (1 << id_len) + current_frame_id - (1 << diff_len)
+            };
+
+            if invalid {
+                self.ref_frame_marking[i] = false;
+            }
+        }
+    }
+
+    /// Saves the state of the current frame in the reference slots selected
+    /// by `refresh_frame_flags`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#reference-frame-update-process
+    pub(crate) fn update_ref_frames(
+        &mut self,
+        refresh_frame_flags: u32,
+        frame_type: FrameType,
+        showable_frame: bool,
+    ) {
+        for i in 0..NUM_REF_FRAMES as usize {
+            if (refresh_frame_flags >> i) & 1 == 1 {
+                self.ref_frame_marking[i] = true;
+                self.ref_frame_id[i] = self.current_frame_id;
+                self.ref_frame_type[i] = Some(frame_type);
+                self.ref_showable_frame[i] = showable_frame;
+                self.ref_order_hint[i] = self.order_hint;
+                self.ref_upscaled_width[i] = self.upscaled_width;
+                self.ref_frame_width[i] = self.frame_width;
+                self.ref_frame_height[i] = self.frame_height;
+                self.ref_render_width[i] = self.render_width;
+                self.ref_render_height[i] = self.render_height;
+                self.ref_segmentation_params[i] = self.segmentation_params;
+                self.ref_loop_filter_params[i] = self.loop_filter_params;
+                self.ref_global_motion_params[i] = self.global_motion_params;
+                self.ref_film_grain_params[i] = self.film_grain_params.clone();
+            }
+        }
+    }
+
+    /// Derives the remaining reference frames from `last_frame_idx` and
+    /// `gold_frame_idx` when `frame_refs_short_signaling` is set, filling
+    /// `ref_frame_idx`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#set-frame-refs-process
+    pub fn set_frame_refs(&mut self, last_frame_idx: u8, gold_frame_idx: u8) {
+        let mut ref_frame_idx = [NONE; REFS_PER_FRAME as usize];
+        ref_frame_idx[0] = last_frame_idx as i8;
+        ref_frame_idx[(GOLDEN_FRAME - LAST_FRAME) as usize] = gold_frame_idx as i8;
+
+        let mut used_frame = [false; NUM_REF_FRAMES as usize];
+        used_frame[last_frame_idx as usize] = true;
+        used_frame[gold_frame_idx as usize] = true;
+
+        let cur_frame_hint = 1 << (self.order_hint_bits.max(1) - 1);
+        let mut shifted_order_hints = [0i32; NUM_REF_FRAMES as usize];
+        for (i, hint) in shifted_order_hints.iter_mut().enumerate() {
+            *hint =
+                cur_frame_hint + self.get_relative_dist(self.ref_order_hint[i], self.order_hint);
+        }
+
+        // find_latest_backward(), find_earliest_backward() and
+        // find_latest_forward() only differ in which side of the current frame
+        // they search and in how ties are broken.
+        let find = |used_frame: &[bool], backward: bool, latest: bool| {
+            let mut found: Option<(usize, i32)> = None;
+            for (i, &hint) in shifted_order_hints.iter().enumerate() {
+                if used_frame[i] || (hint >= cur_frame_hint) != backward {
+                    continue;
+                }
+
+                let better = match found {
+                    None => true,
+                    Some((_, v)) if latest => hint >= v,
+                    Some((_, v)) => hint < v,
+                };
+
+                if better {
+                    found = Some((i, hint));
+                }
+            }
+
+            found.map(|(i, _)| i)
+        };
+
+        if let Some(i) = find(&used_frame, true, true) {
+            ref_frame_idx[(ALTREF_FRAME - LAST_FRAME) as usize] = i as i8;
+            used_frame[i] = true;
+        }
+
+        if let Some(i) = find(&used_frame, true, false) {
+            ref_frame_idx[(BWDREF_FRAME - LAST_FRAME) as usize] = i as i8;
+            used_frame[i] = true;
+        }
+
+        if let Some(i) = find(&used_frame, true, false) {
+            ref_frame_idx[(ALTREF2_FRAME - LAST_FRAME) as usize] = i as i8;
+            used_frame[i] = true;
+        }
+
+        for ref_frame in [
+            LAST2_FRAME,
+            LAST3_FRAME,
+            BWDREF_FRAME,
+            ALTREF2_FRAME,
+            ALTREF_FRAME,
+        ] {
+            let slot = (ref_frame - LAST_FRAME) as usize;
+            if ref_frame_idx[slot] < 0
+                && let Some(i) = find(&used_frame, false, true)
+            {
+                ref_frame_idx[slot] = i as i8;
+                used_frame[i] = true;
+            }
+        }
+
+        // Finally, any remaining references are set to the reference frame with
+        // smallest output order.
+        let mut earliest: Option<(usize, i32)> = None;
+        for (i, &hint) in shifted_order_hints.iter().enumerate() {
+            if earliest.map(|(_, v)| hint < v).unwrap_or(true) {
+                earliest = Some((i, hint));
+            }
+        }
+
+        let earliest = earliest.map(|(i, _)| i as i8).unwrap_or(0);
+        for (i, idx) in ref_frame_idx.iter().enumerate() {
+            self.ref_frame_idx[i] = if *idx < 0 { earliest } else { *idx } as u8;
+        }
+    }
 }
@@ -1,8 +1,11 @@
-use super::{Buffer, ObuContext, ObuError, ObuUnknownError};
+use super::{
+    Buffer, Obu, ObuContext, ObuError, ObuParser, ObuUnknownError, ParseMode, level::Level,
+};
 
-use crate::constants::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS};
+use crate::constants::{BUFFER_POOL_MAX_SIZE, SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ColorPrimaries {
     Bt709,
     Unspecified,
@@ -35,12 +38,13 @@ impl TryFrom<u8> for ColorPrimaries {
             11 => Self::Smpte431,
             12 => Self::Smpte432,
             22 => Self::Ebu3213,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::ColorPrimaries)),
+            _ => return Err(ObuUnknownError::ColorPrimaries.into()),
         })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TransferCharacteristics {
     Bt709,
     Unspecified,
@@ -83,12 +87,13 @@ impl TryFrom<u8> for TransferCharacteristics {
             16 => Self::Smpte2084,
             17 => Self::Smpte428,
             18 => Self::Hlg,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::TransferCharacteristics)),
+            _ => return Err(ObuUnknownError::TransferCharacteristics.into()),
         })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MatrixCoefficients {
     Identity,
     Bt709,
@@ -125,12 +130,13 @@ impl TryFrom<u8> for MatrixCoefficients {
             12 => Self::ChromatNcl,
             13 => Self::ChromatCl,
             14 => Self::Ictcp,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::MatrixCoefficients)),
+            _ => return Err(ObuUnknownError::MatrixCoefficients.into()),
         })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ChromaSamplePosition {
     Unknown,
     Vertical,
@@ -145,12 +151,35 @@ impl TryFrom<u8> for ChromaSamplePosition {
             0 => Self::Unknown,
             1 => Self::Vertical,
             2 => Self::Colocated,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::ChromaSamplePosition)),
+            _ => return Err(ObuUnknownError::ChromaSamplePosition.into()),
         })
     }
 }
 
-#[derive(Debug, Clone)]
+/// Chroma format derived from `mono_chrome`, `subsampling_x` and
+/// `subsampling_y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ChromaSubsampling {
+    Yuv420,
+    Yuv422,
+    Yuv444,
+    Monochrome,
+}
+
+impl std::fmt::Display for ChromaSubsampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Yuv420 => "4:2:0",
+            Self::Yuv422 => "4:2:2",
+            Self::Yuv444 => "4:4:4",
+            Self::Monochrome => "4:0:0",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ColorConfig {
     pub high_bitdepth: bool,
     pub twelve_bit: bool,
@@ -167,6 +196,20 @@ pub struct ColorConfig {
 }
 
 impl ColorConfig {
+    /// NumPlanes, a monochrome stream only codes the luma plane.
+    pub fn num_planes(&self) -> u8 {
+        if self.mono_chrome { 1 } else { 3 }
+    }
+
+    /// BitDepth
+    pub fn bit_depth(&self) -> u8 {
+        match (self.high_bitdepth, self.twelve_bit) {
+            (true, true) => 12,
+            (true, false) => 10,
+            _ => 8,
+        }
+    }
+
     pub fn decode(
         ctx: &mut ObuContext,
         buf: &mut Buffer,
@@ -207,11 +250,11 @@ impl ColorConfig {
             if color_description_present {
                 (
                     // color_primaries	f(8)
-                    ColorPrimaries::try_from(buf.get_bits(8) as u8)?,
+                    ColorPrimaries::try_from(buf.get_bits_u8(8))?,
                     // transfer_characteristics	f(8)
-                    TransferCharacteristics::try_from(buf.get_bits(8) as u8)?,
+                    TransferCharacteristics::try_from(buf.get_bits_u8(8))?,
                     // matrix_coefficients	f(8)
-                    MatrixCoefficients::try_from(buf.get_bits(8) as u8)?,
+                    MatrixCoefficients::try_from(buf.get_bits_u8(8))?,
                 )
             } else {
                 (
@@ -227,6 +270,9 @@ impl ColorConfig {
         let mut chroma_sample_position = None;
 
         if mono_chrome {
+            // Neither the subsampling nor the chroma sample position is coded,
+            // there are no chroma planes.
+            //
             // color_range f(1)
             color_range = buf.get_bit();
             subsampling_x = true;
@@ -283,14 +329,36 @@ impl ColorConfig {
 
             if subsampling_x && subsampling_y {
                 // chroma_sample_position	f(2)
-                chroma_sample_position =
-                    Some(ChromaSamplePosition::try_from(buf.get_bits(2) as u8)?);
+                chroma_sample_position = Some(ChromaSamplePosition::try_from(buf.get_bits_u8(2))?);
             }
         };
 
         // separate_uv_delta_q	f(1)
         let separate_uv_delta_q = buf.get_bit();
 
+        // The identity matrix is used for RGB, which is never subsampled.
+        if matrix_coefficients == MatrixCoefficients::Identity
+            && (subsampling_x || subsampling_y)
+            && ctx.mode == ParseMode::Strict
+        {
+            return Err(ObuUnknownError::MatrixCoefficients.into());
+        }
+
+        // The sRGB color description always selects 4:4:4, which only the
+        // high profile and 12 bit professional streams can carry. The main
+        // profile is 4:2:0 and the professional profile 4:2:2 below 12 bits.
+        let profile_supported = match profile {
+            SequenceProfile::Main => subsampling_x && subsampling_y,
+            SequenceProfile::High => !subsampling_x && !subsampling_y,
+            SequenceProfile::Professional => {
+                ctx.bit_depth == 12 || (subsampling_x && !subsampling_y)
+            }
+        };
+
+        if !profile_supported && ctx.mode == ParseMode::Strict {
+            return Err(ObuUnknownError::Profile.into());
+        }
+
         Ok(Self {
             high_bitdepth,
             twelve_bit,
@@ -309,6 +377,7 @@ impl ColorConfig {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum SequenceProfile {
     Main,
     High,
@@ -323,17 +392,19 @@ impl TryFrom<u8> for SequenceProfile {
             0 => Self::Main,
             1 => Self::High,
             2 => Self::Professional,
-            _ => return Err(ObuError::Unknown(ObuUnknownError::Profile)),
+            _ => return Err(ObuUnknownError::Profile.into()),
         })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct EqualPictureInterval {
     pub num_ticks_per_picture: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TimingInfo {
     pub num_units_in_display_tick: u32,
     pub time_scale: u32,
@@ -341,6 +412,16 @@ pub struct TimingInfo {
 }
 
 impl TimingInfo {
+    /// DispCT, the duration of a display clock tick in seconds. `None` when
+    /// time_scale is 0, which is not allowed.
+    pub fn display_clock_tick(&self) -> Option<f64> {
+        if self.time_scale == 0 {
+            None
+        } else {
+            Some(self.num_units_in_display_tick as f64 / self.time_scale as f64)
+        }
+    }
+
     pub fn decode(buf: &mut Buffer<'_>) -> Self {
         // num_units_in_display_tick f(32)
         let num_units_in_display_tick = buf.get_bits(32);
@@ -366,7 +447,8 @@ impl TimingInfo {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DecoderModelInfo {
     pub buffer_delay_length: u8,
     pub num_units_in_decoding_tick: u32,
@@ -378,18 +460,19 @@ impl DecoderModelInfo {
     pub fn decode(buf: &mut Buffer<'_>) -> Self {
         Self {
             // buffer_delay_length_minus_1 f(5)
-            buffer_delay_length: buf.get_bits(5) as u8 + 1,
+            buffer_delay_length: buf.get_bits_u8(5) + 1,
             // num_units_in_decoding_tick f(32)
             num_units_in_decoding_tick: buf.get_bits(32),
             // buffer_removal_time_length_minus_1 f(5)
-            buffer_removal_time_length: buf.get_bits(5) as u8 + 1,
+            buffer_removal_time_length: buf.get_bits_u8(5) + 1,
             // frame_presentation_time_length_minus_1 f(5)
-            frame_presentation_time_length: buf.get_bits(5) as u8 + 1,
+            frame_presentation_time_length: buf.get_bits_u8(5) + 1,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OperatingParametersInfo {
     pub decoder_buffer_delay: u32,
     pub encoder_buffer_delay: u32,
@@ -410,16 +493,21 @@ impl OperatingParametersInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OperatingPoint {
     pub idc: u16,
     pub level_idx: u8,
     pub tier: bool,
     pub operating_parameters_info: Option<OperatingParametersInfo>,
+    /// InitialDisplayDelay, initial_display_delay_minus_1 + 1. When it is not
+    /// coded initial_display_delay_minus_1 is BUFFER_POOL_MAX_SIZE - 1, the
+    /// delay is then `BUFFER_POOL_MAX_SIZE`.
     pub initial_display_delay: u8,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FrameIdNumbersPresent {
     pub delta_frame_id_length: u8,
     pub additional_frame_id_length: u8,
@@ -429,14 +517,15 @@ impl FrameIdNumbersPresent {
     pub fn decode(buf: &mut Buffer<'_>) -> Self {
         Self {
             // delta_frame_id_length_minus_2	f(4)
-            delta_frame_id_length: buf.get_bits(4) as u8 + 2,
+            delta_frame_id_length: buf.get_bits_u8(4) + 2,
             // additional_frame_id_length_minus_1	f(3)
-            additional_frame_id_length: buf.get_bits(4) as u8 + 1,
+            additional_frame_id_length: buf.get_bits_u8(3) + 1,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SequenceHeader {
     pub seq_profile: SequenceProfile,
     pub still_picture: bool,
@@ -447,7 +536,9 @@ pub struct SequenceHeader {
     pub operating_points: Vec<OperatingPoint>,
     pub frame_width_bits: u8,
     pub frame_height_bits: u8,
+    /// max_frame_width_minus_1, see `SequenceHeader::max_width`.
     pub max_frame_width: u16,
+    /// max_frame_height_minus_1, see `SequenceHeader::max_height`.
     pub max_frame_height: u16,
     pub frame_id_numbers_present: Option<FrameIdNumbersPresent>,
     pub use_128x128_superblock: bool,
@@ -462,6 +553,7 @@ pub struct SequenceHeader {
     pub enable_ref_frame_mvs: bool,
     pub seq_choose_screen_content_tools: bool,
     pub seq_force_screen_content_tools: u8,
+    pub seq_choose_integer_mv: bool,
     pub seq_force_integer_mv: u8,
     pub enable_superres: bool,
     pub enable_cdef: bool,
@@ -471,9 +563,135 @@ pub struct SequenceHeader {
 }
 
 impl SequenceHeader {
+    /// The maximum frame width in the coded video sequence.
+    pub fn max_width(&self) -> u32 {
+        self.max_frame_width as u32 + 1
+    }
+
+    /// The maximum frame height in the coded video sequence.
+    pub fn max_height(&self) -> u32 {
+        self.max_frame_height as u32 + 1
+    }
+
+    /// Index of the operating point containing the most layers, an
+    /// operating point with an idc of 0 contains every layer. Ties go to the
+    /// lowest index.
+    pub fn highest_operating_point(&self) -> usize {
+        let layers = |idc: u16| if idc == 0 { u32::MAX } else { idc.count_ones() };
+
+        let mut highest = 0;
+        for (i, operating_point) in self.operating_points.iter().enumerate() {
+            if layers(operating_point.idc) > layers(self.operating_points[highest].idc) {
+                highest = i;
+            }
+        }
+
+        highest
+    }
+
+    /// timing_info_present_flag, the timing info is coded before the
+    /// decoder model info and the operating points.
+    pub fn timing_info_present_flag(&self) -> bool {
+        self.timing_info.is_some()
+    }
+
+    /// decoder_model_info_present_flag, only coded when timing info is
+    /// present. Frame headers code temporal_point_info and
+    /// buffer_removal_time only when it is set.
+    pub fn decoder_model_info_present_flag(&self) -> bool {
+        self.decoder_model_info.is_some()
+    }
+
+    /// The width and height of a superblock in luma samples, 128 or 64.
+    pub fn superblock_size(&self) -> u32 {
+        if self.use_128x128_superblock { 128 } else { 64 }
+    }
+
+    /// The number of temporal and spatial layers of the stream, derived from
+    /// the highest temporal_id and spatial_id (maxTemporalId, maxSpatialId)
+    /// selected by the operating points. A stream whose operating_point_idc
+    /// values are all 0 has a single layer, `(1, 1)`.
+    pub fn max_layers(&self) -> (u8, u8) {
+        let idc = self
+            .operating_points
+            .iter()
+            .fold(0, |idc, operating_point| idc | operating_point.idc);
+
+        let count = |layers: u16| 16 - layers.leading_zeros() as u8;
+        (count(idc & 0xff).max(1), count((idc >> 8) & 0xf).max(1))
+    }
+
+    pub fn monochrome(&self) -> bool {
+        self.color_config.mono_chrome
+    }
+
+    pub fn subsampling(&self) -> ChromaSubsampling {
+        let color_config = &self.color_config;
+        if color_config.mono_chrome {
+            ChromaSubsampling::Monochrome
+        } else {
+            match (color_config.subsampling_x, color_config.subsampling_y) {
+                (true, true) => ChromaSubsampling::Yuv420,
+                (true, false) => ChromaSubsampling::Yuv422,
+                // subsampling_y is only coded when subsampling_x is set.
+                _ => ChromaSubsampling::Yuv444,
+            }
+        }
+    }
+
+    /// Number of bits used to code frame ids (idLen), 0 when frame ids are
+    /// not present.
+    pub fn frame_id_length(&self) -> u32 {
+        self.frame_id_numbers_present
+            .as_ref()
+            .map(|it| it.additional_frame_id_length as u32 + it.delta_frame_id_length as u32)
+            .unwrap_or(0)
+    }
+
+    /// Whether a decoder supporting `level` can decode the sequence: the
+    /// level of the first operating point (the one containing the most
+    /// layers), the maximum frame size and, if timing info is present, the
+    /// display rate must all be within the limits of `level`.
+    pub fn fits_level(&self, level: Level) -> bool {
+        let Some(limits) = level.limits() else {
+            return level == Level::MAX_PARAMETERS;
+        };
+
+        if let Some(operating_point) = self.operating_points.first() {
+            let declared = Level::from_seq_level_idx(operating_point.level_idx);
+            if declared != Level::MAX_PARAMETERS && declared > level {
+                return false;
+            }
+        }
+
+        let (width, height) = (self.max_width(), self.max_height());
+        let pic_size = width as u64 * height as u64;
+        if width > limits.max_h_size
+            || height > limits.max_v_size
+            || pic_size > limits.max_pic_size as u64
+        {
+            return false;
+        }
+
+        if let Some(timing_info) = &self.timing_info
+            && let Some(equal_picture_interval) = &timing_info.equal_picture_interval
+        {
+            let ticks = timing_info.num_units_in_display_tick as u128
+                * equal_picture_interval.num_ticks_per_picture as u128;
+            if ticks > 0
+                && pic_size as u128 * timing_info.time_scale as u128
+                    > limits.max_display_rate as u128 * ticks
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
     pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
         // seq_profile f(3)
-        let seq_profile = SequenceProfile::try_from(buf.get_bits(3) as u8)?;
+        let seq_profile = SequenceProfile::try_from(buf.get_bits_u8(3))?;
 
         // still_picture f(1)
         let still_picture = buf.get_bit();
@@ -488,13 +706,15 @@ impl SequenceHeader {
         let mut operating_points = Vec::with_capacity(32);
 
         if reduced_still_picture_header {
+            // A single operating point containing every layer, only its level
+            // is coded.
             operating_points.push(OperatingPoint {
                 idc: 0,
                 // seq_level_idx[ 0 ] f(5)
-                level_idx: buf.get_bits(5) as u8,
+                level_idx: buf.get_bits_u8(5),
                 tier: false,
                 operating_parameters_info: None,
-                initial_display_delay: 10,
+                initial_display_delay: BUFFER_POOL_MAX_SIZE,
             });
         } else {
             // timing_info_present_flag f(1)
@@ -513,13 +733,13 @@ impl SequenceHeader {
             initial_display_delay_present_flag = buf.get_bit();
 
             // operating_points_cnt_minus_1	f(5)
-            let operating_points_cnt = buf.get_bits(5) as u8 + 1;
+            let operating_points_cnt = buf.get_bits_u8(5) + 1;
             for _ in 0..operating_points_cnt as usize {
                 // operating_point_idc[ i ]	f(12)
-                let idc = buf.get_bits(12) as u16;
+                let idc = buf.get_bits_u16(12);
 
                 // seq_level_idx[ i ]	f(5)
-                let level_idx = buf.get_bits(5) as u8;
+                let level_idx = buf.get_bits_u8(5);
                 let tier = if level_idx > 7 {
                     // seq_tier[ i ]	f(1)
                     buf.get_bit()
@@ -544,12 +764,12 @@ impl SequenceHeader {
                     let initial_display_delay_present = buf.get_bit();
                     if initial_display_delay_present {
                         // initial_display_delay_minus_1[ i ]	f(4)
-                        buf.get_bits(4) as u8 + 1
+                        buf.get_bits_u8(4) + 1
                     } else {
-                        10
+                        BUFFER_POOL_MAX_SIZE
                     }
                 } else {
-                    10
+                    BUFFER_POOL_MAX_SIZE
                 };
 
                 operating_points.push(OperatingPoint {
@@ -571,16 +791,16 @@ impl SequenceHeader {
             .idc;
 
         // frame_width_bits_minus_1	f(4)
-        let frame_width_bits = buf.get_bits(4) as u8 + 1;
+        let frame_width_bits = buf.get_bits_u8(4) + 1;
 
         // frame_height_bits_minus_1	f(4)
-        let frame_height_bits = buf.get_bits(4) as u8 + 1;
+        let frame_height_bits = buf.get_bits_u8(4) + 1;
 
         // max_frame_width_minus_1	f(n)
-        let max_frame_width = buf.get_bits(frame_width_bits as usize) as u16;
+        let max_frame_width = buf.get_bits_u16(frame_width_bits as usize);
 
         // max_frame_height_minus_1	f(n)
-        let max_frame_height = buf.get_bits(frame_height_bits as usize) as u16;
+        let max_frame_height = buf.get_bits_u16(frame_height_bits as usize);
 
         let frame_id_numbers_present = if !reduced_still_picture_header {
             // frame_id_numbers_present_flag	f(1)
@@ -611,6 +831,7 @@ impl SequenceHeader {
         let mut enable_ref_frame_mvs = false;
         let mut seq_choose_screen_content_tools = false;
         let mut seq_force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
+        let mut seq_choose_integer_mv = false;
         let mut seq_force_integer_mv = SELECT_INTEGER_MV;
 
         if reduced_still_picture_header {
@@ -647,7 +868,7 @@ impl SequenceHeader {
 
             if seq_force_screen_content_tools > 0 {
                 // seq_choose_integer_mv	f(1)
-                let seq_choose_integer_mv = buf.get_bit();
+                seq_choose_integer_mv = buf.get_bit();
                 if !seq_choose_integer_mv {
                     // seq_force_integer_mv	f(1)
                     seq_force_integer_mv = buf.get_bit() as u8;
@@ -701,6 +922,7 @@ impl SequenceHeader {
             enable_ref_frame_mvs,
             seq_choose_screen_content_tools,
             seq_force_screen_content_tools,
+            seq_choose_integer_mv,
             seq_force_integer_mv,
             enable_superres,
             enable_cdef,
@@ -710,3 +932,37 @@ impl SequenceHeader {
         })
     }
 }
+
+impl TryFrom<&[u8]> for SequenceHeader {
+    type Error = ObuError;
+
+    /// Decodes a standalone sequence header OBU, header included, e.g. the
+    /// `configOBUs` of an `av1C` box.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        match ObuParser::default().parse(&mut Buffer::new(bytes))? {
+            Obu::SequenceHeader(sequence_header) => Ok(sequence_header),
+            _ => Err(ObuError::NotFoundSequenceHeader),
+        }
+    }
+}
+
+impl std::fmt::Display for SequenceHeader {
+    /// A one line summary, e.g. `Main 1920x1080 8bit 4:2:0 level 4.0`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {}x{} {}bit {}",
+            self.seq_profile,
+            self.max_width(),
+            self.max_height(),
+            self.color_config.bit_depth(),
+            self.subsampling(),
+        )?;
+
+        if let Some(operating_point) = self.operating_points.first() {
+            write!(f, " level {}", Level::from_seq_level_idx(operating_point.level_idx))?;
+        }
+
+        Ok(())
+    }
+}
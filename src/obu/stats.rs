@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use super::{frame_header::FrameType, Obu, ObuType};
+
+/// Totals collected by `ObuParser` over everything it has parsed.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseStats {
+    /// Number of OBUs of each type.
+    pub obu_count: HashMap<ObuType, usize>,
+    /// Total size of the OBUs of each type, including the OBU header and the
+    /// size field.
+    pub obu_bytes: HashMap<ObuType, usize>,
+    /// Number of frame headers of each frame type.
+    pub frame_count: HashMap<FrameType, usize>,
+    /// Number of OBUs dropped, either because they are not part of the
+    /// selected operating point or because they are ignored (reserved types
+    /// and padding).
+    pub dropped: usize,
+}
+
+impl ParseStats {
+    /// Adds an OBU of `size` bytes, header included, to the totals.
+    pub(crate) fn record(&mut self, obu: &Obu, r#type: ObuType, size: usize) {
+        *self.obu_count.entry(r#type).or_default() += 1;
+        *self.obu_bytes.entry(r#type).or_default() += size;
+
+        let frame_header = match obu {
+            Obu::FrameHeader(frame_header) => Some(frame_header),
+            Obu::Frame(frame) => Some(&frame.header),
+            _ => None,
+        };
+
+        if let Some(frame_header) = frame_header {
+            let frame_type = frame_header.uncompressed_header.frame_type;
+            *self.frame_count.entry(frame_type).or_default() += 1;
+        }
+
+        if obu.is_drop() {
+            self.dropped += 1;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::{Obu, ObuError, ObuParser, parse_obu_header};
+
+use crate::buffer::Buffer;
+
+/// Incremental Open Bitstream Unit parser.
+///
+/// Network sources (RTP, WebRTC, ...) deliver the bitstream in chunks that
+/// don't line up with OBU boundaries. Bytes are accumulated with `push` and
+/// complete OBUs are handed out by `pull`, a partially received OBU is kept
+/// until the rest of it arrives.
+///
+/// Note: Only the low overhead bitstream format is supported, every OBU must
+/// have `obu_has_size_field` set.
+#[derive(Default)]
+pub struct StreamingObuParser {
+    pub parser: ObuParser,
+    data: Vec<u8>,
+}
+
+impl StreamingObuParser {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Returns the next complete OBU, or `None` if more bytes are needed.
+    pub fn pull(&mut self) -> Option<Result<Obu, ObuError>> {
+        let size = match self.next_obu_size()? {
+            Ok(size) => size,
+            Err(e) => {
+                // Without obu_size there is no way to find the next OBU, the
+                // pending bytes are useless.
+                self.data.clear();
+                return Some(Err(e));
+            }
+        };
+
+        let result = self.parser.parse(&mut Buffer::new(&self.data[..size]));
+        self.data.drain(..size);
+
+        Some(result)
+    }
+
+    /// Total size of the OBU at the front of the pending bytes, including the
+    /// header and the size field, if all of it has been received.
+    fn next_obu_size(&self) -> Option<Result<usize, ObuError>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let mut buf = Buffer::new(&self.data);
+        let obu_size = match parse_obu_header(&mut buf) {
+            Ok((_, Some(obu_size))) => obu_size,
+            Ok((_, None)) => return Some(Err(ObuError::MissingObuSize)),
+            // The extension byte or the last byte of obu_size is missing.
+            Err(ObuError::UnexpectedEof) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let size = buf.get_position() / 8 + obu_size;
+        if self.data.len() < size {
+            None
+        } else {
+            Some(Ok(size))
+        }
+    }
+}
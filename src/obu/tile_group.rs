@@ -1,2 +1,144 @@
+use super::{Buffer, ObuContext, ObuError, ParseMode};
+
+/// The coded data of a tile, a byte range of the buffer the tile group was
+/// parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TileData {
+    /// Offset in bytes from the start of the buffer.
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl TileData {
+    /// The coded bytes of the tile, `source` is the buffer the tile group
+    /// was parsed from. Empty if the tile is not within `source`.
+    pub fn bytes<'a>(&self, source: &'a [u8]) -> &'a [u8] {
+        source
+            .get(self.offset..self.offset + self.len)
+            .unwrap_or_default()
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tile-group-obu-syntax
 #[derive(Debug, Clone)]
-pub struct TileGroup {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TileGroup {
+    pub tg_start: u32,
+    pub tg_end: u32,
+    /// Each tile in the group, from `tg_start` to `tg_end`.
+    pub tiles: Vec<TileData>,
+    /// Whether this group carries the last tile of the frame, all the tiles
+    /// of the frame have been received.
+    pub frame_tiles_complete: bool,
+}
+
+impl TileGroup {
+    /// The coded bytes of the tiles of the group, from the start of the
+    /// first tile to the end of the last one, `source` is the buffer the tile
+    /// group was parsed from. The tile_size_minus_1 fields between the tiles
+    /// are included, see `TileData::bytes` for the bytes of a single tile.
+    /// Empty if the tiles are not within `source`.
+    pub fn tile_data<'a>(&self, source: &'a [u8]) -> &'a [u8] {
+        match (self.tiles.first(), self.tiles.last()) {
+            (Some(first), Some(last)) => source
+                .get(first.offset..last.offset + last.len)
+                .unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer, size: usize) -> Result<Self, ObuError> {
+        if !ctx.seen_frame_header {
+            return Err(ObuError::NotFoundFrameHeader);
+        }
+
+        let tile_info = &ctx.tile_info;
+        let num_tiles = tile_info.num_tiles();
+        let start_position = buf.get_position();
+
+        let tile_start_and_end_present = if num_tiles > 1 {
+            // tile_start_and_end_present_flag	f(1)
+            buf.get_bit()
+        } else {
+            false
+        };
+
+        // A single tile group covering a single tile carries no range.
+        let (tg_start, tg_end) = if num_tiles <= 1 || !tile_start_and_end_present {
+            (0, num_tiles.saturating_sub(1))
+        } else {
+            let tile_bits = (tile_info.tile_cols_log2 + tile_info.tile_rows_log2) as usize;
+            (
+                // tg_start	f(tileBits)
+                buf.get_bits(tile_bits),
+                // tg_end	f(tileBits)
+                buf.get_bits(tile_bits),
+            )
+        };
+
+        if tg_start > tg_end || tg_end >= num_tiles {
+            return Err(ObuError::InvalidTileRange);
+        }
+
+        // The tile groups of a frame are sent in order, each one starting
+        // right after the end of the previous one.
+        if tg_start != ctx.next_tile_num && ctx.mode == ParseMode::Strict {
+            return Err(ObuError::InvalidTileRange);
+        }
+
+        buf.byte_alignment();
+
+        let header_bytes = (buf.get_position() - start_position) / 8;
+        let mut size = size.saturating_sub(header_bytes);
+
+        let mut tiles = Vec::with_capacity((tg_end - tg_start + 1) as usize);
+        for tile_num in tg_start..=tg_end {
+            let tile_size = if tile_num == tg_end {
+                size
+            } else {
+                let tile_size_bytes = tile_info.tile_size_bytes as usize;
+
+                // tile_size_minus_1	le(TileSizeBytes)
+                let mut tile_size = buf.get_le(tile_size_bytes) as usize + 1;
+
+                // A tile can't extend past the end of the tile group.
+                let available = size.saturating_sub(tile_size_bytes);
+                if tile_size > available {
+                    if ctx.mode == ParseMode::Strict {
+                        return Err(ObuError::ObuSizeOverrun);
+                    }
+
+                    tile_size = available;
+                }
+
+                size = available - tile_size;
+                tile_size
+            };
+
+            // The coded tile data is not decoded.
+            tiles.push(TileData {
+                offset: buf.get_position() / 8,
+                len: tile_size,
+            });
+
+            buf.seek_bits(tile_size * 8);
+        }
+
+        ctx.next_tile_num = tg_end + 1;
+
+        // The frame ends with its last tile, the next tile group belongs to a
+        // new frame header.
+        let frame_tiles_complete = tg_end == num_tiles - 1;
+        if frame_tiles_complete {
+            ctx.seen_frame_header = false;
+        }
+
+        Ok(Self {
+            tg_start,
+            tg_end,
+            tiles,
+            frame_tiles_complete,
+        })
+    }
+}
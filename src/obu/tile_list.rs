@@ -1,6 +1,7 @@
 use super::Buffer;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TileListEntry {
     pub anchor_frame_idx: u8,
     pub anchor_tile_row: u8,
@@ -12,16 +13,16 @@ pub struct TileListEntry {
 impl TileListEntry {
     pub fn decode(buf: &mut Buffer) -> Self {
         // anchor_frame_idx	f(8)
-        let anchor_frame_idx = buf.get_bits(8) as u8;
+        let anchor_frame_idx = buf.get_bits_u8(8);
 
         // anchor_tile_row	f(8)
-        let anchor_tile_row = buf.get_bits(8) as u8;
+        let anchor_tile_row = buf.get_bits_u8(8);
 
         // anchor_tile_col	f(8)
-        let anchor_tile_col = buf.get_bits(8) as u8;
+        let anchor_tile_col = buf.get_bits_u8(8);
 
         // tile_data_size_minus_1	f(16)
-        let tile_data_size = buf.get_bits(16) as u16 + 1;
+        let tile_data_size = buf.get_bits_u16(16) + 1;
 
         // coded_tile_data	f(N)
         let coded_tile_data = buf.get_bits(8 * tile_data_size as usize);
@@ -36,6 +37,7 @@ impl TileListEntry {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TileList {
     pub output_frame_width_in_tiles: u8,
     pub output_frame_height_in_tiles: u8,
@@ -45,10 +47,10 @@ pub struct TileList {
 impl TileList {
     pub fn decode(buf: &mut Buffer) -> Self {
         // output_frame_width_in_tiles_minus_1	f(8)
-        let output_frame_width_in_tiles = buf.get_bits(8) as u8;
+        let output_frame_width_in_tiles = buf.get_bits_u8(8);
 
         // output_frame_height_in_tiles_minus_1	f(8)
-        let output_frame_height_in_tiles = buf.get_bits(8) as u8;
+        let output_frame_height_in_tiles = buf.get_bits_u8(8);
 
         // tile_count_minus_1	f(16)
         let tile_count = buf.get_bits(16) as usize;
@@ -0,0 +1,81 @@
+/// Bitstream writer, the counterpart of `Buffer`.
+#[derive(Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+    bit_pos: usize,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current position in the bitstream, in bits.
+    pub fn get_position(&self) -> usize {
+        self.buf.len() * 8 - (8 - self.bit_pos) % 8
+    }
+
+    pub fn put_bit(&mut self, value: bool) {
+        if self.bit_pos == 0 {
+            self.buf.push(0);
+        }
+
+        if value {
+            let last = self.buf.len() - 1;
+            self.buf[last] |= 1 << (7 - self.bit_pos);
+        }
+
+        self.bit_pos = (self.bit_pos + 1) % 8;
+    }
+
+    /// Unsigned n-bit number written directly to the bitstream. The bits are
+    /// written from high to low order.
+    pub fn put_bits(&mut self, count: usize, value: u32) {
+        assert!(count > 0 && count <= 32);
+
+        for i in 0..count {
+            self.put_bit((value >> (count - i - 1)) & 1 == 1);
+        }
+    }
+
+    /// Unsigned integer represented by a variable number of little-endian
+    /// bytes.
+    ///
+    /// Note: This syntax element will only be written when the bitstream
+    /// position is byte aligned.
+    pub fn put_leb128(&mut self, mut value: u32) {
+        assert_eq!(self.bit_pos, 0);
+
+        loop {
+            let mut byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value != 0 {
+                byte |= 0x80;
+            }
+
+            self.buf.push(byte);
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        assert_eq!(self.bit_pos, 0);
+
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Writes the stop bit followed by zero bits up to the next byte
+    /// boundary.
+    pub fn trailing_bits(&mut self) {
+        self.put_bit(true);
+        while self.bit_pos != 0 {
+            self.put_bit(false);
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
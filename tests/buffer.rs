@@ -0,0 +1,340 @@
+use av1_obu_parser::buffer::Buffer;
+
+/// The bit by bit reading `get_bits` used to do.
+fn get_bits_reference(buf: &mut Buffer, count: usize) -> u32 {
+    let mut value = 0;
+    for i in 0..count {
+        value |= (buf.get_bit() as u32) << (count - i - 1);
+    }
+
+    value
+}
+
+fn bytes() -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn get_bits_matches_bit_by_bit_reads() {
+    let bytes = bytes();
+
+    for offset in 0..8 {
+        let mut buf = Buffer::new(&bytes);
+        let mut reference = Buffer::new(&bytes);
+        buf.seek_bits(offset);
+        reference.seek_bits(offset);
+
+        let mut count = 0;
+        while reference.get_position() + 32 <= bytes.len() * 8 {
+            assert_eq!(
+                buf.get_bits(count),
+                get_bits_reference(&mut reference, count)
+            );
+            assert_eq!(buf.get_position(), reference.get_position());
+
+            count = (count + 7) % 33;
+        }
+    }
+}
+
+#[test]
+fn get_bits_reads_up_to_the_last_bit() {
+    let bytes = [0xa5, 0x5a];
+    let mut buf = Buffer::new(&bytes);
+
+    assert_eq!(buf.get_bits(3), 0b101);
+    assert_eq!(buf.get_bits(13), 0b0_0101_0101_1010);
+    assert_eq!(buf.get_bits(0), 0);
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[cfg(not(feature = "checked-buffer"))]
+#[test]
+#[should_panic(expected = "read past the end of the buffer")]
+fn get_bits_panics_past_the_end() {
+    let bytes = [0xff, 0xff];
+    let mut buf = Buffer::new(&bytes);
+
+    buf.seek_bits(4);
+    buf.get_bits(13);
+}
+
+fn leb128(mut value: u64, padding: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+
+        bytes.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+
+    bytes.extend(std::iter::repeat_n(0x55, padding));
+    bytes
+}
+
+#[test]
+fn get_leb128_with_and_without_lookahead() {
+    for value in [0, 1, 127, 128, 300, 16383, 16384, 1 << 28, u32::MAX as u64] {
+        for padding in [0, 8] {
+            let bytes = leb128(value, padding);
+            let mut buf = Buffer::new(&bytes);
+
+            assert_eq!(buf.get_leb128(), Some(value as u32));
+            assert_eq!(buf.remaining_bytes(), padding);
+        }
+    }
+}
+
+#[test]
+fn get_leb128_rejects_invalid_values() {
+    for padding in [0, 8] {
+        let bytes = leb128(u32::MAX as u64 + 1, padding);
+        let mut buf = Buffer::new(&bytes);
+
+        assert_eq!(buf.get_leb128(), None);
+        assert_eq!(buf.remaining_bytes(), padding);
+
+        let mut bytes = vec![0x80; 8];
+        bytes.extend(std::iter::repeat_n(0x00, padding));
+        let mut buf = Buffer::new(&bytes);
+
+        assert_eq!(buf.get_leb128(), None);
+        assert_eq!(buf.remaining_bytes(), padding);
+    }
+}
+
+#[test]
+fn get_leb128_truncated() {
+    for len in 1..8 {
+        let bytes = vec![0x80; len];
+        let mut buf = Buffer::new(&bytes);
+
+        assert_eq!(buf.get_leb128(), None);
+        assert_eq!(buf.remaining_bytes(), len);
+    }
+
+    assert_eq!(Buffer::new(&[]).get_leb128(), None);
+}
+
+#[test]
+fn sized_reads() {
+    // 1, 0xff, 0xffff, 0xffffffff, 0
+    let mut buf = Buffer::new(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x80]);
+
+    assert!(buf.get_flag());
+    assert_eq!(buf.get_bits_u8(8), u8::MAX);
+    assert_eq!(buf.get_bits_u16(16), u16::MAX);
+    assert_eq!(buf.get_bits(32), u32::MAX);
+    assert!(!buf.get_flag());
+    assert_eq!(buf.get_position(), 58);
+
+    // The widest reads of an unaligned buffer.
+    let mut buf = Buffer::new(&[0x7f, 0xff, 0x80, 0x00, 0x00, 0x01]);
+    assert!(!buf.get_flag());
+    assert_eq!(buf.get_bits_u16(16), u16::MAX);
+    assert_eq!(buf.get_bits(31), 1);
+}
+
+#[test]
+fn get_ns() {
+    // 10, 11 1
+    let mut buf = Buffer::new(&[0b1011_1000]);
+
+    assert_eq!(buf.get_ns(5), 2);
+    assert_eq!(buf.get_ns(5), 4);
+    assert_eq!(buf.get_position(), 5);
+}
+
+#[test]
+fn get_ns_power_of_two() {
+    // n = 1 has a single value and reads no bits, a power of two reads
+    // exactly FloorLog2(n) bits.
+    //
+    // 101, 1 1
+    let mut buf = Buffer::new(&[0b1011_1000]);
+
+    assert_eq!(buf.get_ns(1), 0);
+    assert_eq!(buf.get_position(), 0);
+    assert_eq!(buf.get_ns(8), 5);
+    assert_eq!(buf.get_position(), 3);
+
+    // n = 2^k + 1 needs the extra bit for the largest values only.
+    assert_eq!(buf.get_ns(3), 2);
+    assert_eq!(buf.get_position(), 5);
+}
+
+#[test]
+fn get_signed_subexp_with_ref() {
+    // The range of a 12 bits global motion parameter, the expected values
+    // follow decode_signed_subexp_with_ref of the spec.
+    //
+    // 0 101, 0 000
+    let mut buf = Buffer::new(&[0b0101_0000]);
+
+    assert_eq!(buf.get_signed_subexp_with_ref(-4096, 4097, 0), -3);
+    assert_eq!(buf.get_signed_subexp_with_ref(-4096, 4097, 0), 0);
+
+    // 111111 0 00101100, 0 000
+    let mut buf = Buffer::new(&[0b1111_1100, 0b0101_1000, 0b0000_0000]);
+
+    assert_eq!(buf.get_signed_subexp_with_ref(-4096, 4097, 4000), 3796);
+    assert_eq!(buf.get_signed_subexp_with_ref(-4096, 4097, 4000), 4000);
+    assert_eq!(buf.get_position(), 19);
+}
+
+#[cfg(feature = "checked-buffer")]
+#[test]
+fn checked_reads_return_out_of_bounds() {
+    use av1_obu_parser::buffer::OutOfBounds;
+
+    let bytes = [0xa5, 0x5a];
+    let mut buf = Buffer::new(&bytes);
+
+    assert_eq!(buf.try_get_bits(3), Ok(0b101));
+    assert_eq!(buf.try_get_bits(14), Err(OutOfBounds));
+    assert_eq!(buf.get_position(), 3);
+
+    assert_eq!(buf.try_get_bits(13), Ok(0b0_0101_0101_1010));
+    assert_eq!(buf.try_get_bits(0), Ok(0));
+    assert_eq!(buf.try_get_bit(), Err(OutOfBounds));
+
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(buf.try_read_bytes(3), Err(OutOfBounds));
+    assert_eq!(buf.try_read_bytes(2), Ok(&bytes[..]));
+}
+
+#[cfg(feature = "checked-buffer")]
+#[test]
+fn reads_past_the_end_are_flagged() {
+    use av1_obu_parser::buffer::OutOfBounds;
+
+    let bytes = [0xff, 0xff];
+    let mut buf = Buffer::new(&bytes);
+
+    buf.seek_bits(4);
+    assert_eq!(buf.check(), Ok(()));
+    assert_eq!(buf.get_bits(13), 0);
+    assert_eq!(buf.check(), Err(OutOfBounds));
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    // The following reads are zeros.
+    assert!(!buf.get_bit());
+    assert_eq!(buf.get_uvlc(), 0);
+    assert_eq!(buf.read_bytes(1), &[] as &[u8]);
+}
+
+#[test]
+fn trailing_bits() {
+    // 101, 1000 0, 0100 0000
+    let mut buf = Buffer::new(&[0b1011_0000, 0b0100_0000]);
+    buf.get_bits(3);
+    assert!(buf.trailing_bits(5));
+    assert_eq!(buf.get_position(), 8);
+
+    // A zero stop bit, the one comes in the padding.
+    assert!(!buf.trailing_bits(8));
+    assert_eq!(buf.get_position(), 16);
+}
+
+#[test]
+fn read_bytes_after_alignment() {
+    let bytes = [0xa0, 0x01, 0x02, 0x03, 0x04, 0x05];
+    let mut buf = Buffer::new(&bytes);
+
+    assert_eq!(buf.get_bits(3), 0b101);
+    buf.byte_alignment();
+
+    // The bytes borrow the bitstream, not the buffer.
+    let payload = buf.read_bytes(3);
+    assert_eq!(buf.get_position(), 32);
+    assert_eq!(buf.remaining_bytes(), 2);
+    assert_eq!(buf.read_bytes(0), &[] as &[u8]);
+    assert_eq!(buf.read_bytes(2), &[0x04, 0x05]);
+    assert_eq!(payload, &[0x01, 0x02, 0x03]);
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[cfg(not(feature = "checked-buffer"))]
+#[test]
+#[should_panic(expected = "read past the end of the buffer")]
+fn read_bytes_panics_past_the_end() {
+    let bytes = [0xa5, 0x5a];
+    let mut buf = Buffer::new(&bytes);
+
+    buf.read_bytes(3);
+}
+
+#[cfg(not(feature = "checked-buffer"))]
+#[test]
+#[should_panic(expected = "read past the end of the buffer")]
+fn get_bytes_panics_past_the_end() {
+    let bytes = [0xa5, 0x5a];
+    let mut buf = Buffer::new(&bytes);
+
+    assert_eq!(buf.get_bytes(1), &[0xa5]);
+    buf.get_bytes(2);
+}
+
+#[test]
+fn new_owned() {
+    use av1_obu_parser::{buffer::OwnedBuffer, obu::ObuParser};
+
+    // The bytes outlive the function that built them.
+    fn temporal_unit() -> OwnedBuffer {
+        Buffer::new_owned(vec![
+            0x12, 0x00, 0x2a, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80,
+        ])
+    }
+
+    let owned = temporal_unit();
+    let mut parser = ObuParser::default();
+    let mut buf = owned.as_buffer();
+    assert!(parser.parse(&mut buf).unwrap().is_temporal_delimiter());
+    assert!(parser.parse(&mut buf).unwrap().as_metadata().is_some());
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    // Every buffer starts at the start of the bitstream.
+    assert_eq!(owned.as_buffer().remaining_bytes(), 10);
+}
+
+#[test]
+fn from_reader() {
+    use av1_obu_parser::obu::{Obu, ObuParser};
+    use std::io::{self, Cursor, Read};
+
+    // A temporal delimiter and HDR CLL metadata.
+    let bytes = [0x12, 0x00, 0x2a, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80];
+    let owned = Buffer::from_reader(Cursor::new(bytes)).unwrap();
+
+    let mut parser = ObuParser::default();
+    let mut buf = owned.as_buffer();
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::Metadata(_))));
+    assert_eq!(owned.into_bytes(), bytes);
+
+    // Read errors are returned as is.
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disconnected"))
+        }
+    }
+
+    let error = Buffer::from_reader(Failing).unwrap_err();
+    assert_eq!(error.to_string(), "disconnected");
+}
@@ -0,0 +1,19 @@
+//! OBUs shared by the tests.
+#![allow(dead_code)]
+
+/// Main profile 1920x1080 8 bit 4:2:0 sequence header with 7 bit order hints.
+pub const SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe6, 0x01,
+];
+
+/// Lossless key frame with 2x2 tiles.
+pub const FRAME: [u8; 21] = [
+    0x32, 0x13, 0x10, 0x00, 0xd0, 0x80, 0x00, 0x00, 0x01, 0x00, 0xaa, 0xbb, 0x01, 0x00, 0xaa, 0xbb,
+    0x01, 0x00, 0xaa, 0xbb, 0xcc,
+];
+
+/// `SEQUENCE_HEADER` with frame ids, delta_frame_id_length_minus_2 is 2 and
+/// additional_frame_id_length_minus_1 is 3 for 8 bit frame ids.
+pub const FRAME_ID_SEQUENCE_HEADER: [u8; 14] = [
+    0x0a, 0x0c, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x79, 0x30, 0x13, 0xcc, 0x02,
+];
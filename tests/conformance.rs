@@ -0,0 +1,134 @@
+//! Parses every OBU of the test vectors in `tests/fixtures`, and of the
+//! directory named by `AV1_CONFORMANCE_DIR` when it is set, for example a
+//! checkout of the aomedia test vectors.
+//!
+//! Both low overhead bitstreams (`.obu`) and IVF files (`.ivf`) are
+//! accepted. When a `<file>.json` sits next to a vector, the last sequence
+//! header of the vector must serialize to the same value.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{Obu, ObuError, ObuParser, sequence_header::SequenceHeader},
+};
+
+fn vectors(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|it| it.to_str()),
+                Some("obu" | "ivf")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths
+}
+
+/// Splits an IVF file into the payloads of its frames.
+fn ivf_frames(bytes: &[u8]) -> Vec<&[u8]> {
+    assert_eq!(&bytes[..4], b"DKIF", "not an ivf file");
+
+    let header_size = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+    let mut frames = Vec::new();
+    let mut offset = header_size;
+    while offset + 12 <= bytes.len() {
+        let size = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+
+        offset += 12;
+        frames.push(&bytes[offset..offset + size]);
+        offset += size;
+    }
+
+    frames
+}
+
+/// Parses all OBUs of a chunk, OBUs the parser doesn't support are skipped.
+fn parse_all(
+    parser: &mut ObuParser,
+    bytes: &[u8],
+    sequence_header: &mut Option<SequenceHeader>,
+) -> Result<usize, ObuError> {
+    let mut buf = Buffer::new(bytes);
+    let mut count = 0;
+    while buf.remaining_bytes() > 0 {
+        match parser.parse(&mut buf) {
+            Ok(Obu::SequenceHeader(it)) => *sequence_header = Some(it),
+            Ok(_) | Err(ObuError::Unsupported(_)) => (),
+            Err(e) => return Err(e),
+        }
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+fn check(path: &Path) {
+    let bytes = fs::read(path).unwrap();
+    let chunks = if path.extension().and_then(|it| it.to_str()) == Some("ivf") {
+        ivf_frames(&bytes)
+    } else {
+        vec![bytes.as_slice()]
+    };
+
+    let mut parser = ObuParser::default();
+    let mut sequence_header = None;
+    let mut count = 0;
+    for chunk in chunks {
+        match parse_all(&mut parser, chunk, &mut sequence_header) {
+            Ok(it) => count += it,
+            Err(e) => panic!("{}: {:?}", path.display(), e),
+        }
+    }
+
+    assert!(count > 0, "{}: no obu found", path.display());
+    assert_eq!(parser.sequence_header(), sequence_header.as_ref());
+
+    let mut expected = path.as_os_str().to_owned();
+    expected.push(".json");
+    if let Ok(expected) = fs::read_to_string(&expected) {
+        let expected: serde_json::Value = serde_json::from_str(&expected).unwrap();
+        let sequence_header = sequence_header
+            .unwrap_or_else(|| panic!("{}: no sequence header found", path.display()));
+
+        assert_eq!(
+            serde_json::to_value(&sequence_header).unwrap(),
+            expected,
+            "{}: sequence header mismatch",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn fixtures() {
+    let paths = vectors(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        check(&path);
+    }
+}
+
+#[test]
+fn test_vectors() {
+    let Some(dir) = std::env::var_os("AV1_CONFORMANCE_DIR") else {
+        eprintln!("AV1_CONFORMANCE_DIR is not set, skipping");
+        return;
+    };
+
+    for path in vectors(Path::new(&dir)) {
+        check(&path);
+    }
+}
@@ -0,0 +1,452 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{
+        ObuError, ObuParser, ParseMode, frame_header::FrameType, sequence_header::SequenceHeader,
+    },
+};
+
+mod common;
+
+use common::{FRAME, FRAME_ID_SEQUENCE_HEADER, SEQUENCE_HEADER};
+
+#[test]
+fn context_before_any_obu() {
+    let parser = ObuParser::default();
+    let ctx = parser.context();
+
+    assert_eq!(ctx.mode(), ParseMode::Strict);
+    assert!(ctx.sequence_header().is_none());
+    assert!(ctx.sequence_header_bytes().is_none());
+    assert!(ctx.frame_header().is_none());
+    assert_eq!(ctx.frames_parsed(), 0);
+    assert_eq!(ctx.ref_frame_type(0), None);
+}
+
+#[test]
+fn context_after_sequence_header() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let ctx = parser.context();
+
+    assert_eq!(ctx.sequence_header().unwrap().max_width(), 1920);
+    assert_eq!(ctx.sequence_header_bytes(), Some(&SEQUENCE_HEADER[..]));
+    assert_eq!(ctx.bit_depth(), 8);
+    assert_eq!(ctx.num_planes(), 3);
+    assert_eq!(ctx.operating_point(), (0, 0));
+    assert!(ctx.obu_header_extension().is_none());
+}
+
+#[test]
+fn sequence_header_bytes_parse_to_the_same_sequence_header() {
+    let mut parser = ObuParser::default();
+    for bytes in [&[0x12, 0x00][..], &FRAME_ID_SEQUENCE_HEADER] {
+        parser.parse(&mut Buffer::new(bytes)).unwrap();
+    }
+
+    let ctx = parser.context();
+    let bytes = ctx.sequence_header_bytes().unwrap();
+    assert_eq!(bytes, FRAME_ID_SEQUENCE_HEADER);
+    assert_eq!(
+        SequenceHeader::try_from(bytes).unwrap(),
+        *ctx.sequence_header().unwrap()
+    );
+
+    // A new sequence header replaces the cached bytes.
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let ctx = parser.context();
+    assert_eq!(ctx.sequence_header_bytes(), Some(&SEQUENCE_HEADER[..]));
+}
+
+#[test]
+fn padded_sequence_header_keeps_the_references() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+
+    // The same sequence header with a zero byte of padding.
+    let mut padded = SEQUENCE_HEADER.to_vec();
+    padded[1] += 1;
+    padded.push(0x00);
+    parser.parse(&mut Buffer::new(&padded)).unwrap();
+
+    let ctx = parser.context();
+    assert_eq!(ctx.sequence_header_bytes(), Some(&padded[..]));
+    assert_eq!(ctx.ref_frame_type(0), Some(FrameType::KeyFrame));
+    assert_eq!(ctx.frames_parsed(), 1);
+
+    // A different sequence header starts over.
+    parser
+        .parse(&mut Buffer::new(&FRAME_ID_SEQUENCE_HEADER))
+        .unwrap();
+
+    let ctx = parser.context();
+    assert_eq!(ctx.ref_frame_type(0), None);
+    assert_eq!(ctx.frames_parsed(), 0);
+}
+
+#[test]
+fn context_after_key_frame() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    let ctx = parser.context();
+
+    assert_eq!(ctx.frames_parsed(), 1);
+    assert_eq!(
+        ctx.frame_header().unwrap().frame_type(),
+        FrameType::KeyFrame
+    );
+    assert!(ctx.frame_is_intra());
+    assert_eq!(ctx.order_hint(), 0);
+    assert_eq!(ctx.frame_dimensions(), (1920, 1080));
+    assert_eq!(ctx.dimensions(), (1920, 1080));
+    assert_eq!(ctx.render_dimensions(), (1920, 1080));
+    assert_eq!(ctx.mi_dimensions(), (480, 270));
+    assert_eq!(ctx.lossless(), (true, true));
+    assert_eq!(ctx.tile_info().tile_cols, 2);
+    assert_eq!(ctx.tile_info().tile_rows, 2);
+
+    for i in 0..8 {
+        assert_eq!(ctx.ref_frame_type(i), Some(FrameType::KeyFrame));
+        assert_eq!(ctx.ref_order_hint(i), 0);
+        assert_eq!(ctx.ref_dimensions(i), (1920, 1080));
+    }
+}
+
+#[test]
+fn context_snapshot() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let snapshot = parser.context().clone();
+
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    assert_eq!(parser.context().frames_parsed(), 1);
+    assert_eq!(
+        parser.context().ref_frame_type(0),
+        Some(FrameType::KeyFrame)
+    );
+
+    // The snapshot is still the state after the sequence header.
+    assert_eq!(
+        snapshot.sequence_header(),
+        parser.context().sequence_header()
+    );
+    assert_eq!(snapshot.sequence_header_bytes(), Some(&SEQUENCE_HEADER[..]));
+    assert!(snapshot.frame_header().is_none());
+    assert_eq!(snapshot.frames_parsed(), 0);
+    for i in 0..8 {
+        assert_eq!(snapshot.ref_frame_type(i), None);
+        assert!(!snapshot.ref_valid(i));
+    }
+}
+
+#[test]
+fn relative_dist_wraps_around() {
+    let mut parser = ObuParser::default();
+    assert_eq!(parser.context().get_relative_dist(4, 1), 0);
+
+    // 7 bit order hints, the distances are within -64..64.
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let ctx = parser.context();
+    assert_eq!(ctx.get_relative_dist(4, 1), 3);
+    assert_eq!(ctx.get_relative_dist(1, 4), -3);
+    assert_eq!(ctx.get_relative_dist(1, 127), 2);
+    assert_eq!(ctx.get_relative_dist(127, 1), -2);
+    assert_eq!(ctx.get_relative_dist(63, 0), 63);
+    assert_eq!(ctx.get_relative_dist(64, 0), -64);
+    assert_eq!(ctx.get_relative_dist(0, 64), -64);
+}
+
+/// A shown single tile lossless key frame, order_hint 0.
+const KEY_FRAME: [u8; 8] = [0x32, 0x06, 0x10, 0x00, 0x80, 0x00, 0x00, 0x00];
+
+/// An inter frame with order_hint 4 referencing slot 0 only and refreshing
+/// slot 1.
+const INTER_FRAME_4: [u8; 13] = [
+    0x32, 0x0b, 0x30, 0x09, 0xc0, 0x80, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// An inter frame with order_hint 2 between the two frames above,
+/// ref_frame_idx [0, 1, 0, 0, 1, 1, 1], refreshing slot 2.
+const INTER_FRAME_2: [u8; 13] = [
+    0x32, 0x0b, 0x30, 0x05, 0xc1, 0x00, 0x80, 0x49, 0x2c, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn order_hints_over_a_gop() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    assert!((0..8).all(|i| !parser.context().ref_valid(i)));
+
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+    let ctx = parser.context();
+    assert!((0..8).all(|i| ctx.ref_valid(i) && ctx.ref_order_hint(i) == 0));
+    assert_eq!(ctx.order_hints(), [0; 8]);
+
+    parser.parse(&mut Buffer::new(&INTER_FRAME_4)).unwrap();
+    let ctx = parser.context();
+    assert_eq!(ctx.order_hint(), 4);
+    assert_eq!(ctx.order_hints(), [0; 8]);
+    assert_eq!(ctx.ref_order_hint(1), 4);
+
+    parser.parse(&mut Buffer::new(&INTER_FRAME_2)).unwrap();
+    let ctx = parser.context();
+    let header = &ctx.frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::InterFrame);
+    assert!(header.coded_lossless);
+    assert!(!header.reduced_tx_set);
+
+    assert_eq!(ctx.order_hint(), 2);
+    assert_eq!(ctx.order_hints(), [0, 0, 4, 0, 0, 4, 4, 4]);
+    assert_eq!(
+        (0..8).map(|i| ctx.ref_order_hint(i)).collect::<Vec<_>>(),
+        [0, 4, 2, 0, 0, 0, 0, 0]
+    );
+
+    // A shown key frame resets every slot and OrderHints.
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+    let ctx = parser.context();
+    assert!((0..8).all(|i| ctx.ref_valid(i) && ctx.ref_order_hint(i) == 0));
+    assert_eq!(ctx.order_hints(), [0; 8]);
+}
+
+/// `SEQUENCE_HEADER` with enable_ref_frame_mvs set.
+const REF_FRAME_MVS_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x0b, 0xe6, 0x01,
+];
+
+/// An inter frame with order_hint 1 and use_ref_frame_mvs set.
+const REF_FRAME_MVS_INTER_FRAME: [u8; 13] = [
+    0x32, 0x0b, 0x30, 0x03, 0xc0, 0x80, 0x00, 0x00, 0x2e, 0x00, 0x01, 0x00, 0x00,
+];
+
+/// An error resilient inter frame with order_hint 2, use_ref_frame_mvs is not
+/// coded.
+const ERROR_RESILIENT_INTER_FRAME: [u8; 20] = [
+    0x32, 0x12, 0x38, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x60,
+    0x00, 0x10, 0x00, 0x00,
+];
+
+#[test]
+fn use_ref_frame_mvs_gating() {
+    let use_ref_frame_mvs = |parser: &ObuParser| {
+        let header = &parser.context().frame_header().unwrap().uncompressed_header;
+        assert!(header.reduced_tx_set);
+
+        header.use_ref_frame_mvs
+    };
+
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&REF_FRAME_MVS_SEQUENCE_HEADER))
+        .unwrap();
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+
+    parser
+        .parse(&mut Buffer::new(&REF_FRAME_MVS_INTER_FRAME))
+        .unwrap();
+    assert!(use_ref_frame_mvs(&parser));
+
+    // Error resilient frames don't code use_ref_frame_mvs, the following
+    // syntax elements stay in sync.
+    parser
+        .parse(&mut Buffer::new(&ERROR_RESILIENT_INTER_FRAME))
+        .unwrap();
+    let header = &parser.context().frame_header().unwrap().uncompressed_header;
+    assert!(header.error_resilient_mode);
+    assert!(!use_ref_frame_mvs(&parser));
+
+    // Neither do the frames of a sequence without enable_ref_frame_mvs.
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+    parser.parse(&mut Buffer::new(&INTER_FRAME_4)).unwrap();
+    let header = &parser.context().frame_header().unwrap().uncompressed_header;
+    assert!(!header.use_ref_frame_mvs);
+}
+
+const TEMPORAL_DELIMITER: [u8; 2] = [0x12, 0x00];
+
+/// A frame header OBU of a hidden showable key frame for
+/// `FRAME_ID_SEQUENCE_HEADER`, current_frame_id 5, refreshing slot 0.
+const HIDDEN_KEY_FRAME_HEADER: [u8; 9] = [0x1a, 0x07, 0x08, 0x05, 0x05, 0x01, 0x60, 0x00, 0x10];
+
+/// A frame header OBU of a shown intra only frame, current_frame_id 9,
+/// refreshing slot 1.
+const INTRA_ONLY_FRAME_HEADER: [u8; 9] = [0x1a, 0x07, 0x50, 0x12, 0x0c, 0x04, 0xc0, 0x00, 0x20];
+
+/// A frame header OBU showing slot 0 again, display_frame_id 5.
+const SHOW_EXISTING_FRAME_HEADER: [u8; 4] = [0x1a, 0x02, 0x80, 0x58];
+
+#[test]
+fn show_existing_key_frame_loads_the_frame_id() {
+    let mut parser = ObuParser::default();
+    for obu in [
+        &FRAME_ID_SEQUENCE_HEADER[..],
+        &HIDDEN_KEY_FRAME_HEADER,
+        &TEMPORAL_DELIMITER,
+        &INTRA_ONLY_FRAME_HEADER,
+        &TEMPORAL_DELIMITER,
+    ] {
+        parser.parse(&mut Buffer::new(obu)).unwrap();
+    }
+
+    assert_eq!(parser.context().current_frame_id(), 9);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&SHOW_EXISTING_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.frame_to_show_map_idx, Some(0));
+    assert_eq!(header.frame_type, FrameType::KeyFrame);
+    assert_eq!(header.display_frame_id, Some(5));
+
+    // Every slot is refreshed with the shown key frame.
+    let ctx = parser.context();
+    assert_eq!(ctx.current_frame_id(), 5);
+    assert!((0..8).all(|i| ctx.ref_frame_type(i) == Some(FrameType::KeyFrame)));
+
+    // A key frame is shown at most once.
+    parser.parse(&mut Buffer::new(&TEMPORAL_DELIMITER)).unwrap();
+    assert_eq!(
+        parser
+            .parse(&mut Buffer::new(&SHOW_EXISTING_FRAME_HEADER))
+            .unwrap_err(),
+        ObuError::FrameNotShowable
+    );
+}
+
+/// `FRAME_ID_SEQUENCE_HEADER`, a shown key frame with current_frame_id 5.
+const SHOWN_KEY_FRAME_HEADER: [u8; 8] = [0x1a, 0x06, 0x10, 0x14, 0x01, 0x80, 0x00, 0x40];
+
+/// An inter frame with current_frame_id 6, all its references are slot 0
+/// with a delta_frame_id_minus_1 of 0.
+const DELTA_FRAME_ID_FRAME_HEADER: [u8; 17] = [
+    0x1a, 0x0f, 0x30, 0x0c, 0x03, 0xc0, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x00, 0x00,
+    0x20,
+];
+
+/// `DELTA_FRAME_ID_FRAME_HEADER` with a delta_frame_id_minus_1 of 1, the
+/// references would be frame 4.
+const WRONG_DELTA_FRAME_ID_FRAME_HEADER: [u8; 17] = [
+    0x1a, 0x0f, 0x30, 0x0c, 0x03, 0xc0, 0x80, 0x40, 0x81, 0x02, 0x04, 0x08, 0x12, 0xc0, 0x00, 0x00,
+    0x20,
+];
+
+#[test]
+fn delta_frame_id_of_the_references() {
+    for (mode, frame_header, valid) in [
+        (ParseMode::Strict, DELTA_FRAME_ID_FRAME_HEADER, true),
+        (ParseMode::Strict, WRONG_DELTA_FRAME_ID_FRAME_HEADER, false),
+        (ParseMode::Lenient, WRONG_DELTA_FRAME_ID_FRAME_HEADER, true),
+    ] {
+        let mut parser = ObuParser::default();
+        parser.set_mode(mode);
+        for obu in [
+            &FRAME_ID_SEQUENCE_HEADER[..],
+            &SHOWN_KEY_FRAME_HEADER,
+            &TEMPORAL_DELIMITER,
+        ] {
+            parser.parse(&mut Buffer::new(obu)).unwrap();
+        }
+
+        let result = parser.parse(&mut Buffer::new(&frame_header));
+        if valid {
+            let obu = result.unwrap();
+            assert_eq!(
+                obu.as_frame_header().unwrap().frame_type(),
+                FrameType::InterFrame
+            );
+            assert_eq!(parser.context().current_frame_id(), 6);
+        } else {
+            assert_eq!(result.unwrap_err(), ObuError::FrameIdMismatch);
+        }
+    }
+}
+
+/// Inter frames refreshing slot 1 to 6 with the order hints 8, 4, 2, 6, 7 and
+/// 3, all their references are slot 0.
+const REFRESH_FRAMES: [[u8; 13]; 6] = [
+    [
+        0x32, 0x0b, 0x30, 0x11, 0xc0, 0x80, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x32, 0x0b, 0x30, 0x09, 0xc1, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x32, 0x0b, 0x30, 0x05, 0xc2, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x32, 0x0b, 0x30, 0x0d, 0xc4, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x32, 0x0b, 0x30, 0x0f, 0xc8, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x32, 0x0b, 0x30, 0x07, 0xd0, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    ],
+];
+
+/// An inter frame with order_hint 5 and frame_refs_short_signaling,
+/// last_frame_idx 2 and gold_frame_idx 3.
+const SHORT_SIGNALING_FRAME: [u8; 11] = [
+    0x32, 0x09, 0x30, 0x0b, 0xc0, 0x29, 0x96, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn set_frame_refs_with_short_signaling() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+    for frame in &REFRESH_FRAMES {
+        parser.parse(&mut Buffer::new(frame)).unwrap();
+    }
+
+    let ctx = parser.context();
+    assert_eq!(
+        (0..8).map(|i| ctx.ref_order_hint(i)).collect::<Vec<_>>(),
+        [0, 8, 4, 2, 6, 7, 3, 0]
+    );
+
+    parser
+        .parse(&mut Buffer::new(&SHORT_SIGNALING_FRAME))
+        .unwrap();
+
+    // LAST_FRAME and GOLDEN_FRAME are signaled (slots 2 and 3), ALTREF_FRAME
+    // is the furthest backward reference (slot 1), BWDREF_FRAME and
+    // ALTREF2_FRAME the closest ones (slots 4 and 5). LAST2_FRAME and
+    // LAST3_FRAME are the closest forward references left (slot 6, then slot
+    // 7 which ties with slot 0).
+    assert_eq!(parser.context().order_hints(), [0, 4, 3, 0, 2, 6, 7, 8]);
+}
+
+/// A switch frame with order_hint 3, the ref_order_hint of every slot is 0
+/// and the 1920x1080 frame size is coded explicitly.
+const SWITCH_FRAME: [u8; 21] = [
+    0x32, 0x13, 0x70, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1d, 0xfe, 0x1b,
+    0x96, 0x00, 0x01, 0x00, 0x00,
+];
+
+#[test]
+fn switch_frame_forced_fields() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&KEY_FRAME)).unwrap();
+
+    let mut buf = Buffer::new(&SWITCH_FRAME);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::SwitchFrame);
+    assert!(header.error_resilient_mode);
+    assert_eq!(header.refresh_frame_flags, 0xff);
+    assert_eq!(header.primary_ref_frame, 7);
+    assert!(header.is_random_access_point());
+    assert!(header.reduced_tx_set);
+
+    let ctx = parser.context();
+    assert_eq!(ctx.frame_dimensions(), (1920, 1080));
+    assert!((0..8).all(|i| ctx.ref_frame_type(i) == Some(FrameType::SwitchFrame)));
+    assert!((0..8).all(|i| ctx.ref_order_hint(i) == 3));
+}
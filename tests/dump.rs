@@ -0,0 +1,37 @@
+//! Runs the `dump` example on a test vector.
+
+use std::{fs, process::Command};
+
+use serde_json::{Value, json};
+
+#[test]
+fn dump_example_prints_every_obu() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let output = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args(["run", "--quiet", "--example", "dump", "--features", "serde"])
+        .args(["--", "--input", "tests/fixtures/seq_1080p.obu"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let obus = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    let sequence_header: Value = serde_json::from_str(
+        &fs::read_to_string(format!("{manifest_dir}/tests/fixtures/seq_1080p.obu.json")).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        obus,
+        [
+            json!("TemporalDelimiter"),
+            json!({ "SequenceHeader": sequence_header }),
+            json!({ "Metadata": { "HdrCll": { "max_cll": 1000, "max_fall": 400 } } }),
+        ]
+    );
+}
@@ -0,0 +1,51 @@
+use av1_obu_parser::obu::{ObuError, ObuType, ObuUnknownError};
+
+fn frame_type(value: u8) -> Result<u8, ObuUnknownError> {
+    if value < 4 {
+        Ok(value)
+    } else {
+        Err(ObuUnknownError::FrameType)
+    }
+}
+
+fn frame_types(values: &[u8]) -> Result<Vec<u8>, ObuError> {
+    let mut frame_types = Vec::new();
+    for &value in values {
+        frame_types.push(frame_type(value)?);
+    }
+
+    Ok(frame_types)
+}
+
+#[test]
+fn unknown_errors_convert_with_the_question_mark() {
+    assert_eq!(frame_types(&[0, 3]), Ok(vec![0, 3]));
+    assert_eq!(
+        frame_types(&[0, 4]),
+        Err(ObuError::Unknown(ObuUnknownError::FrameType))
+    );
+    assert_eq!(
+        ObuError::from(ObuUnknownError::Profile),
+        ObuError::Unknown(ObuUnknownError::Profile)
+    );
+}
+
+#[test]
+fn errors_display() {
+    assert_eq!(
+        ObuError::from(ObuUnknownError::FrameType).to_string(),
+        "invalid value of frame_type"
+    );
+    assert_eq!(
+        ObuError::UnexpectedEof.to_string(),
+        "unexpected end of the bitstream"
+    );
+    assert_eq!(
+        ObuError::InvalidObuLength.to_string(),
+        "unit overruns the enclosing unit"
+    );
+    assert_eq!(
+        ObuError::Unsupported(ObuType::TileList).to_string(),
+        "unsupported obu type TileList"
+    );
+}
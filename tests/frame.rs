@@ -0,0 +1,1310 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{
+        Obu, ObuError, ObuParser, ObuUnknownError, ParseMode,
+        frame_header::{
+            FilmGrainParams, FrameRestorationType, FrameType, GlobalMotionParams, LoopFilterParams,
+            ReferenceMode, TemporalPointInfo, TxMode, WarpModelType, read_delta_q,
+            read_global_param,
+        },
+        sequence_header::TimingInfo,
+        tile_group::TileData,
+    },
+};
+
+mod common;
+
+use common::{FRAME, SEQUENCE_HEADER};
+
+/// The same frame with only the tiles 0 and 1, the other ones follow in
+/// `TILE_GROUP`.
+const PARTIAL_FRAME: [u8; 13] = [
+    0x32, 0x0b, 0x10, 0x00, 0xd0, 0x80, 0x00, 0x88, 0x01, 0x00, 0xaa, 0xbb, 0xcc,
+];
+
+/// The tiles 2 and 3 of `PARTIAL_FRAME`.
+const TILE_GROUP: [u8; 8] = [0x22, 0x06, 0xd8, 0x01, 0x00, 0xaa, 0xbb, 0xcc];
+
+/// A redundant frame header OBU with the frame header of `FRAME`.
+const REDUNDANT_FRAME_HEADER: [u8; 7] = [0x3a, 0x05, 0x10, 0x00, 0xd0, 0x80, 0x01];
+
+/// A redundant frame header OBU with a different order_hint.
+const MISMATCHED_FRAME_HEADER: [u8; 7] = [0x3a, 0x05, 0x10, 0x10, 0xd0, 0x80, 0x01];
+
+/// A frame header OBU of a key frame using quantizer matrices, qm_y is 5 and
+/// qm_u is 9.
+const QM_FRAME_HEADER: [u8; 8] = [0x1a, 0x06, 0x10, 0x00, 0xd0, 0x80, 0x0a, 0xc9];
+
+/// A frame header OBU of a lossless key frame with reduced_tx_set set right
+/// after the delta parameters.
+const LOSSLESS_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0xd0, 0x80, 0x03];
+
+/// A frame header OBU of a key frame with base_q_idx 100, loop filter deltas
+/// and one CDEF strength.
+const LOOP_FILTER_FRAME_HEADER: [u8; 17] = [
+    0x1a, 0x0f, 0x10, 0x00, 0xd0, 0xb2, 0x00, 0x51, 0x81, 0x88, 0xbf, 0xb0, 0x08, 0x34, 0x9c, 0x60,
+    0x38,
+];
+
+/// A frame header OBU of a lossless key frame with non-uniform tile spacing,
+/// two tile columns of 10 and 20 superblocks and a single tile row.
+const NON_UNIFORM_TILES_FRAME_HEADER: [u8; 9] =
+    [0x1a, 0x07, 0x10, 0x00, 0x2f, 0xff, 0x20, 0x00, 0xc0];
+
+/// A frame header OBU of a shown, hence error resilient, lossless key frame
+/// with disable_cdf_update set.
+const DISABLE_CDF_UPDATE_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x18, 0x01, 0x00, 0x01, 0x80];
+
+/// `DISABLE_CDF_UPDATE_FRAME_HEADER` with CDF updates enabled and
+/// disable_frame_end_update_cdf coded as 0.
+const CDF_UPDATE_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0x80, 0x00, 0xc0];
+
+/// A frame header OBU of a lossless key frame with frame_size_override_flag
+/// set, 1280x720 coded on the 11 bits of the 1920x1080 sequence header.
+const OVERRIDE_FRAME_HEADER: [u8; 9] = [0x1a, 0x07, 0x12, 0x02, 0x7f, 0xac, 0xf2, 0x00, 0x03];
+
+/// A frame header OBU of a lossless key frame in 2x2 uniform tiles, with
+/// context_update_tile_id = 3 and tile_size_bytes_minus_1 = 2.
+const CONTEXT_UPDATE_TILE_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0xd7, 0x00, 0x03];
+
+/// A frame header OBU showing the frame in slot 0 again, with
+/// show_existing_frame set.
+const SHOW_EXISTING_FRAME_HEADER: [u8; 3] = [0x1a, 0x01, 0x80];
+
+fn parser(mode: ParseMode) -> ObuParser {
+    let mut parser = ObuParser::default();
+    parser.set_mode(mode);
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser
+}
+
+#[test]
+fn frame_obu_ends_the_frame() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let frame = parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    assert!(frame.as_frame().unwrap().tile_group.frame_tiles_complete);
+
+    // There is no frame left to repeat the header of.
+    let obu = parser
+        .parse(&mut Buffer::new(&REDUNDANT_FRAME_HEADER))
+        .unwrap();
+    assert!(matches!(obu, Obu::Drop));
+
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&TILE_GROUP)),
+        Err(ObuError::NotFoundFrameHeader)
+    ));
+}
+
+#[test]
+fn decoded_frame() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    let frame = obu.as_frame().unwrap();
+    assert_eq!(frame.frame_type(), FrameType::KeyFrame);
+    assert!(frame.show_frame());
+    assert_eq!(frame.header.num_tiles(), 4);
+    assert!(frame.header.uncompressed_header.coded_lossless);
+
+    let tile_group = &frame.tile_group;
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (0, 3));
+    assert_eq!(
+        tile_group.tiles,
+        [
+            TileData { offset: 10, len: 2 },
+            TileData { offset: 14, len: 2 },
+            TileData { offset: 18, len: 2 },
+            TileData { offset: 20, len: 1 },
+        ]
+    );
+    assert_eq!(frame.tile_data(&FRAME), &FRAME[10..]);
+}
+
+#[test]
+fn frame_obu_must_contain_every_tile() {
+    let mut parser = parser(ParseMode::Strict);
+
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&PARTIAL_FRAME)),
+        Err(ObuError::InvalidTileRange)
+    ));
+}
+
+#[test]
+fn redundant_frame_header_after_partial_frame_obu() {
+    let mut parser = parser(ParseMode::Lenient);
+
+    let frame = parser.parse(&mut Buffer::new(&PARTIAL_FRAME)).unwrap();
+    assert!(!frame.as_frame().unwrap().tile_group.frame_tiles_complete);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&REDUNDANT_FRAME_HEADER))
+        .unwrap();
+    assert!(obu.as_frame_header().is_some());
+
+    let obu = parser.parse(&mut Buffer::new(&TILE_GROUP)).unwrap();
+    assert!(matches!(obu, Obu::TileGroup(it) if it.frame_tiles_complete));
+    assert_eq!(parser.frames_parsed(), 1);
+}
+
+#[test]
+fn mismatched_redundant_frame_header() {
+    let mut parser = parser(ParseMode::Lenient);
+    parser.parse(&mut Buffer::new(&PARTIAL_FRAME)).unwrap();
+    parser.set_mode(ParseMode::Strict);
+
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&MISMATCHED_FRAME_HEADER)),
+        Err(ObuError::FrameHeaderMismatch)
+    ));
+}
+
+#[test]
+fn frame_type_round_trip() {
+    for value in 0..4 {
+        assert_eq!(u8::from(FrameType::try_from(value).unwrap()), value);
+    }
+
+    assert!(matches!(
+        FrameType::try_from(4),
+        Err(ObuError::Unknown(ObuUnknownError::FrameType))
+    ));
+}
+
+#[test]
+fn quantizer_matrix_levels() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser.parse(&mut Buffer::new(&QM_FRAME_HEADER)).unwrap();
+    let params = &obu
+        .as_frame_header()
+        .unwrap()
+        .uncompressed_header
+        .quantization_params;
+
+    assert!(params.using_qmatrix);
+    assert_eq!((params.qm_y, params.qm_u), (5, 9));
+    // separate_uv_delta_q is 0 in the sequence header, qm_v is not coded.
+    assert_eq!(params.qm_v, params.qm_u);
+}
+
+#[test]
+fn lossless_frame_skips_loop_filter_params() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&LOSSLESS_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+
+    assert!(header.coded_lossless);
+    assert_eq!(header.loop_filter_params, LoopFilterParams::default());
+    assert_eq!(header.cdef_params.cdef_bits, 0);
+    assert!(!header.lr_params.uses_lr());
+    assert_eq!(header.tx_mode, TxMode::Only4x4);
+
+    // The only bit left after the delta parameters.
+    assert!(header.reduced_tx_set);
+}
+
+/// `LOSSLESS_FRAME_HEADER` with a DeltaQYDc of 1, the loop filter, CDEF and
+/// loop restoration are all off and tx_mode_select is set.
+const NEAR_LOSSLESS_FRAME_HEADER: [u8; 12] = [
+    0x1a, 0x0a, 0x10, 0x00, 0x80, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x05,
+];
+
+#[test]
+fn delta_q_breaks_lossless() {
+    let mut parser = parser(ParseMode::Strict);
+
+    // base_q_idx is 0 but the luma DC quantizer is not.
+    let obu = parser
+        .parse(&mut Buffer::new(&NEAR_LOSSLESS_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.quantization_params.base_q_idx, 0);
+    assert_eq!(header.quantization_params.delta_q_y_dc, 1);
+    assert!(!header.coded_lossless);
+    assert!(!header.all_lossless);
+    assert_eq!(header.tx_mode, TxMode::Select);
+    assert_eq!(parser.context().lossless(), (false, false));
+}
+
+#[test]
+fn loop_filter_params() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&LOOP_FILTER_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(!header.coded_lossless);
+
+    let params = &header.loop_filter_params;
+    assert_eq!(params.loop_filter_level, [10, 12, 3, 4]);
+    assert_eq!(params.loop_filter_sharpness, 2);
+    assert!(params.loop_filter_delta_enabled && params.loop_filter_delta_update);
+    assert_eq!(params.loop_filter_ref_deltas, [-5, 0, 0, 0, -1, 0, -1, -1]);
+    assert_eq!(params.loop_filter_mode_deltas, [0, 3]);
+
+    let cdef_params = &header.cdef_params;
+    assert_eq!((cdef_params.cdef_damping, cdef_params.cdef_bits), (4, 0));
+    assert_eq!(cdef_params.cdef_y_pri_strength[0], 9);
+    assert_eq!(cdef_params.cdef_y_sec_strength[0], 4);
+    assert_eq!(cdef_params.cdef_uv_pri_strength[0], 1);
+    assert_eq!(cdef_params.cdef_uv_sec_strength[0], 2);
+
+    assert_eq!(
+        header.lr_params.frame_restoration_type,
+        [FrameRestorationType::None; 3]
+    );
+    assert_eq!(header.tx_mode, TxMode::Select);
+    assert!(header.reduced_tx_set);
+}
+
+/// A frame header OBU of a single tile key frame with base_q_idx 100 and
+/// allow_intrabc set, tx_mode_select is set.
+const INTRABC_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x16, 0x00, 0xa3, 0x20, 0x1c];
+
+#[test]
+fn intrabc_frame_skips_the_in_loop_filters() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let mut buf = Buffer::new(&INTRABC_FRAME_HEADER);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(header.allow_screen_content_tools);
+    assert!(header.allow_intrabc);
+    assert!(!header.coded_lossless);
+
+    // No CDEF bits are read, a single preset with all strengths equal to 0.
+    assert_eq!(header.cdef_params.cdef_damping, 3);
+    assert_eq!(header.cdef_params.cdef_bits, 0);
+    assert_eq!(header.cdef_params.cdef_y_pri_strength, [0; 8]);
+    assert_eq!(header.cdef_params.cdef_uv_sec_strength, [0; 8]);
+    assert_eq!(header.loop_filter_params.loop_filter_level, [0; 4]);
+    assert!(!header.lr_params.uses_lr());
+
+    // The syntax after the CDEF params is in place.
+    assert_eq!(header.tx_mode, TxMode::Select);
+    assert!(header.reduced_tx_set);
+}
+
+/// `SEQUENCE_HEADER` with enable_restoration unset.
+const NO_RESTORATION_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe4, 0x01,
+];
+
+/// A frame header OBU of a single tile key frame with the loop filter levels
+/// 10, 12, 3 and 4 and a single CDEF preset, tx_mode_select is set.
+const NO_RESTORATION_FRAME_HEADER: [u8; 12] = [
+    0x1a, 0x0a, 0x10, 0x00, 0x8c, 0x80, 0x14, 0x60, 0x62, 0x22, 0x4e, 0x37,
+];
+
+#[test]
+fn restoration_disabled_skips_lr_params() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&NO_RESTORATION_SEQUENCE_HEADER))
+        .unwrap();
+    assert!(!parser.sequence_header().unwrap().enable_restoration);
+
+    let mut buf = Buffer::new(&NO_RESTORATION_FRAME_HEADER);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(!header.coded_lossless);
+    assert_eq!(header.loop_filter_params.loop_filter_level, [10, 12, 3, 4]);
+    assert_eq!(header.cdef_params.cdef_y_pri_strength[0], 9);
+
+    // No LR bits are read, every plane is RESTORE_NONE.
+    assert_eq!(
+        header.lr_params.frame_restoration_type,
+        [FrameRestorationType::None; 3]
+    );
+    assert!(!header.lr_params.uses_lr());
+
+    // The syntax after the LR params is in place.
+    assert_eq!(header.tx_mode, TxMode::Select);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn presentation_time_in_seconds() {
+    // 59.94 fps
+    let timing_info = TimingInfo {
+        num_units_in_display_tick: 1001,
+        time_scale: 60000,
+        equal_picture_interval: None,
+    };
+
+    let temporal_point_info = TemporalPointInfo {
+        frame_presentation_time: 120,
+    };
+
+    let seconds = temporal_point_info.presentation_time(&timing_info).unwrap();
+    assert!((seconds - 2.002).abs() < 1e-9);
+
+    let timing_info = TimingInfo {
+        time_scale: 0,
+        ..timing_info
+    };
+
+    assert_eq!(temporal_point_info.presentation_time(&timing_info), None);
+}
+
+#[test]
+fn non_uniform_tile_spacing() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&NON_UNIFORM_TILES_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    let tile_info = &header.tile_info;
+
+    assert_eq!((tile_info.tile_cols, tile_info.tile_rows), (2, 1));
+    assert_eq!((tile_info.tile_cols_log2, tile_info.tile_rows_log2), (1, 0));
+
+    // 64x64 superblocks are 16 mode info units wide, the columns cover the
+    // 480 units of the frame width.
+    assert_eq!(tile_info.mi_col_starts, [0, 160, 480]);
+    assert_eq!(tile_info.mi_row_starts, [0, 270]);
+    assert_eq!(tile_info.tile_size_bytes, 2);
+
+    // The syntax after the tile info is still in sync.
+    assert!(header.coded_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn context_update_tile_id_and_tile_size_bytes() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&CONTEXT_UPDATE_TILE_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    let tile_info = &header.tile_info;
+
+    // Four tiles code context_update_tile_id in two bits.
+    assert_eq!((tile_info.tile_cols, tile_info.tile_rows), (2, 2));
+    assert_eq!(tile_info.tile_cols_log2 + tile_info.tile_rows_log2, 2);
+    assert_eq!(tile_info.context_update_tile_id, 3);
+    assert_eq!(tile_info.tile_size_bytes, 3);
+
+    // The syntax after the tile info is still in sync.
+    assert!(header.coded_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn cdf_update_flags() {
+    // disable_cdf_update forces disable_frame_end_update_cdf without coding it.
+    let obu = parser(ParseMode::Strict)
+        .parse(&mut Buffer::new(&DISABLE_CDF_UPDATE_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(header.error_resilient_mode);
+    assert!(header.disable_cdf_update);
+    assert!(header.disable_frame_end_update_cdf);
+    assert!(header.coded_lossless);
+    assert!(header.reduced_tx_set);
+
+    // Error resilient frames still code disable_frame_end_update_cdf.
+    let obu = parser(ParseMode::Strict)
+        .parse(&mut Buffer::new(&CDF_UPDATE_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(header.error_resilient_mode);
+    assert!(!header.disable_cdf_update);
+    assert!(!header.disable_frame_end_update_cdf);
+    assert!(header.coded_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn read_translation_global_params() {
+    // Without allow_high_precision_mv the translation of a TRANSLATION model
+    // is coded with 8 bits of magnitude and 2 fractional bits, so 14 bits of
+    // precision are dropped.
+    let bytes = [0b1001_0010, 0b1000_0000];
+    let mut buf = Buffer::new(&bytes);
+    let identity = GlobalMotionParams::default().gm_params[1];
+    assert_eq!(identity, [0, 0, 1 << 16, 0, 0, 1 << 16]);
+
+    let read = |buf: &mut Buffer, idx: usize, prev: i32| {
+        read_global_param(buf, WarpModelType::Translation, idx, prev, false)
+    };
+
+    // 5 and -3 coded relative to the identity model, subexp 10 and 5.
+    let x = read(&mut buf, 0, identity[0]);
+    let y = read(&mut buf, 1, identity[1]);
+    assert_eq!((x, y), (5 << 14, -3 << 14));
+    assert_eq!(buf.get_position(), 9);
+
+    // Repeating the parameter of the previous frame is the cheapest.
+    assert_eq!(read(&mut buf, 0, x), x);
+    assert_eq!(buf.get_position(), 13);
+}
+
+#[test]
+fn frame_size_override() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&OVERRIDE_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!((header.frame_width, header.frame_height), (1280, 720));
+    assert_eq!((header.render_width, header.render_height), (1280, 720));
+    assert!(!header.render_and_frame_size_different);
+
+    let ctx = parser.context();
+    assert_eq!(ctx.sequence_header().unwrap().max_width(), 1920);
+    assert_eq!(ctx.frame_dimensions(), (1280, 720));
+    assert_eq!(ctx.mi_dimensions(), (320, 180));
+
+    // The syntax after the frame size is still in sync.
+    assert!(header.coded_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+/// `LOSSLESS_FRAME_HEADER` with a 1280x720 render size.
+const RENDER_SIZE_FRAME_HEADER: [u8; 11] = [
+    0x1a, 0x09, 0x10, 0x02, 0x09, 0xfe, 0x05, 0x9e, 0xd0, 0x80, 0x03,
+];
+
+#[test]
+fn render_size() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&RENDER_SIZE_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(header.render_and_frame_size_different);
+    assert_eq!((header.frame_width, header.frame_height), (1920, 1080));
+    assert_eq!((header.render_width, header.render_height), (1280, 720));
+    assert!(header.reduced_tx_set);
+
+    let ctx = parser.context();
+    assert_eq!(ctx.frame_dimensions(), (1920, 1080));
+    assert_eq!(ctx.render_dimensions(), (1280, 720));
+}
+
+#[test]
+fn frame_tile_data() {
+    let bytes = [&SEQUENCE_HEADER[..], &FRAME].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    parser.parse(&mut buf).unwrap();
+    let obu = parser.parse(&mut buf).unwrap();
+    let frame = obu.as_frame().unwrap();
+
+    // The tiles start after the OBU header, the frame header and the byte
+    // holding tile_start_and_end_present_flag, and end with the OBU.
+    let tile_data = frame.tile_data(&bytes);
+    assert_eq!(tile_data, &FRAME[10..]);
+    assert_eq!(
+        tile_data.as_ptr(),
+        bytes[SEQUENCE_HEADER.len() + 10..].as_ptr()
+    );
+
+    // The tile sizes are 2 bytes between the tiles.
+    let tiles = frame
+        .tile_group
+        .tiles
+        .iter()
+        .map(|tile| tile.bytes(&bytes))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tiles,
+        [&[0xaa, 0xbb][..], &[0xaa, 0xbb], &[0xaa, 0xbb], &[0xcc]]
+    );
+}
+
+#[test]
+fn frame_header_copies() {
+    let mut parser = parser(ParseMode::Strict);
+
+    parser.parse(&mut Buffer::new(&QM_FRAME_HEADER)).unwrap();
+
+    // The frame header OBUs that follow, until the frame is complete, repeat
+    // the first one.
+    let obu = parser.parse(&mut Buffer::new(&QM_FRAME_HEADER)).unwrap();
+    assert_eq!(
+        obu.as_frame_header().unwrap().frame_type(),
+        FrameType::KeyFrame
+    );
+    assert_eq!(parser.frames_parsed(), 1);
+
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&LOSSLESS_FRAME_HEADER)),
+        Err(ObuError::FrameHeaderMismatch)
+    ));
+}
+
+#[test]
+fn frame_header_after_a_failed_one() {
+    let mut parser = parser(ParseMode::Strict);
+
+    // Slot 0 holds no frame yet.
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&SHOW_EXISTING_FRAME_HEADER)),
+        Err(ObuError::Unknown(ObuUnknownError::FrameTypeRefIndex))
+    ));
+
+    // The failed frame header doesn't start a frame, the next one is not
+    // taken as a copy.
+    let obu = parser.parse(&mut Buffer::new(&QM_FRAME_HEADER)).unwrap();
+    assert_eq!(
+        obu.as_frame_header().unwrap().frame_type(),
+        FrameType::KeyFrame
+    );
+    assert_eq!(parser.frames_parsed(), 1);
+}
+
+/// The frame header of `FRAME` in a frame header OBU.
+const FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0xd0, 0x80, 0x01];
+
+/// The tiles 1 to 3 of `FRAME_HEADER`, 2, 3 and 1 bytes long, only the first
+/// two are prefixed with their size.
+const THREE_TILES_TILE_GROUP: [u8; 13] = [
+    0x22, 0x0b, 0xb8, 0x01, 0x00, 0xaa, 0xbb, 0x02, 0x00, 0xcc, 0xdd, 0xee, 0xff,
+];
+
+#[test]
+fn tile_sizes() {
+    let mut parser = parser(ParseMode::Strict);
+    parser.parse(&mut Buffer::new(&FRAME_HEADER)).unwrap();
+
+    // Tile 0, the last tile of its group, takes the rest of the OBU.
+    let obu = parser
+        .parse(&mut Buffer::new(&[0x22, 0x03, 0x80, 0xaa, 0xbb]))
+        .unwrap();
+    let tile_group = obu.as_tile_group().unwrap();
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (0, 0));
+    assert_eq!(tile_group.tiles, [TileData { offset: 3, len: 2 }]);
+
+    let obu = parser
+        .parse(&mut Buffer::new(&THREE_TILES_TILE_GROUP))
+        .unwrap();
+    let tile_group = obu.as_tile_group().unwrap();
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (1, 3));
+    assert!(tile_group.frame_tiles_complete);
+
+    let tiles = tile_group
+        .tiles
+        .iter()
+        .map(|tile| tile.bytes(&THREE_TILES_TILE_GROUP))
+        .collect::<Vec<_>>();
+    assert_eq!(tiles, [&[0xaa, 0xbb][..], &[0xcc, 0xdd, 0xee], &[0xff]]);
+}
+
+#[test]
+fn one_tile_data_per_tile_of_the_group() {
+    let mut parser = parser(ParseMode::Strict);
+    parser.parse(&mut Buffer::new(&FRAME_HEADER)).unwrap();
+
+    for bytes in [&[0x22, 0x03, 0x80, 0xaa, 0xbb][..], &THREE_TILES_TILE_GROUP] {
+        let obu = parser.parse(&mut Buffer::new(bytes)).unwrap();
+        let tile_group = obu.as_tile_group().unwrap();
+        assert_eq!(
+            tile_group.tiles.len() as u32,
+            tile_group.tg_end - tile_group.tg_start + 1
+        );
+
+        // The tiles are in order and don't overlap.
+        assert!(
+            tile_group
+                .tiles
+                .windows(2)
+                .all(|it| it[0].offset + it[0].len <= it[1].offset)
+        );
+    }
+}
+
+#[test]
+fn tile_size_overruns_the_tile_group() {
+    // The third tile claims 256 bytes, only 3 are left.
+    let mut bytes = FRAME;
+    bytes[16] = 0xff;
+
+    assert!(matches!(
+        parser(ParseMode::Strict).parse(&mut Buffer::new(&bytes)),
+        Err(ObuError::ObuSizeOverrun)
+    ));
+
+    // The tile is cut at the end of the tile group.
+    let obu = parser(ParseMode::Lenient)
+        .parse(&mut Buffer::new(&bytes))
+        .unwrap();
+    let tiles = &obu.as_frame().unwrap().tile_group.tiles;
+    assert_eq!(tiles[2], TileData { offset: 18, len: 3 });
+    assert_eq!(tiles[3], TileData { offset: 21, len: 0 });
+    assert_eq!(tiles[2].bytes(&bytes), [0xaa, 0xbb, 0xcc]);
+    assert!(tiles[3].bytes(&bytes).is_empty());
+
+    // A tile outside of the source has no bytes.
+    assert!(tiles[2].bytes(&bytes[..19]).is_empty());
+    assert!(
+        obu.as_frame()
+            .unwrap()
+            .tile_group
+            .tile_data(&bytes[..19])
+            .is_empty()
+    );
+}
+
+/// An inter frame with allow_screen_content_tools and force_integer_mv set,
+/// allow_high_precision_mv is not coded.
+const INTEGER_MV_INTER_FRAME: [u8; 13] = [
+    0x32, 0x0b, 0x33, 0x01, 0xe0, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x02, 0x00, 0x00,
+];
+
+/// `INTEGER_MV_INTER_FRAME` with force_integer_mv unset.
+const SUBPEL_MV_INTER_FRAME: [u8; 13] = [
+    0x32, 0x0b, 0x32, 0x01, 0xe0, 0x00, 0x00, 0x00, 0x16, 0x00, 0x01, 0x00, 0x00,
+];
+
+/// `INTEGER_MV_INTER_FRAME` with error_resilient_mode set, primary_ref_frame
+/// is not coded and the reference order hints are.
+const ERROR_RESILIENT_INTER_FRAME: [u8; 20] = [
+    0x32, 0x12, 0x3b, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x20,
+    0x00, 0x10, 0x00, 0x00,
+];
+
+#[test]
+fn screen_content_tools_and_integer_mv_per_frame() {
+    let mut parser = parser(ParseMode::Strict);
+    let sequence_header = parser.sequence_header().unwrap();
+    assert_eq!(sequence_header.seq_force_screen_content_tools, 2);
+    assert_eq!(sequence_header.seq_force_integer_mv, 2);
+
+    for (bytes, force_integer_mv) in [
+        (INTEGER_MV_INTER_FRAME, true),
+        (SUBPEL_MV_INTER_FRAME, false),
+    ] {
+        parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+
+        let obu = parser.parse(&mut Buffer::new(&bytes)).unwrap();
+        let header = &obu.as_frame().unwrap().header.uncompressed_header;
+        assert_eq!(header.frame_type, FrameType::InterFrame);
+        assert!(header.allow_screen_content_tools);
+        assert_eq!(header.force_integer_mv, force_integer_mv);
+
+        // The syntax after allow_high_precision_mv is in place.
+        assert!(header.reduced_tx_set);
+    }
+}
+
+#[test]
+fn error_resilient_inter_frame() {
+    let mut parser = parser(ParseMode::Strict);
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+
+    let mut buf = Buffer::new(&ERROR_RESILIENT_INTER_FRAME);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::InterFrame);
+    assert!(header.error_resilient_mode);
+    assert_eq!(header.primary_ref_frame, 7);
+    assert!(!header.use_ref_frame_mvs);
+
+    // disable_frame_end_update_cdf is still coded, here as 0.
+    assert!(!header.disable_frame_end_update_cdf);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn restoration_type_and_reference_mode_values() {
+    // FrameRestorationType follows the semantic values, lr_type is remapped.
+    let types = [
+        FrameRestorationType::None,
+        FrameRestorationType::Wiener,
+        FrameRestorationType::Sgrproj,
+        FrameRestorationType::Switchable,
+    ];
+    for (value, r#type) in types.into_iter().enumerate() {
+        assert_eq!(FrameRestorationType::try_from(value as u8), Ok(r#type));
+    }
+
+    let lr_types = [
+        FrameRestorationType::None,
+        FrameRestorationType::Switchable,
+        FrameRestorationType::Wiener,
+        FrameRestorationType::Sgrproj,
+    ];
+    for (lr_type, r#type) in lr_types.into_iter().enumerate() {
+        assert_eq!(
+            FrameRestorationType::from_lr_type(lr_type as u8),
+            Ok(r#type)
+        );
+    }
+
+    assert_eq!(
+        FrameRestorationType::try_from(4),
+        Err(ObuError::Unknown(ObuUnknownError::FrameRestorationType))
+    );
+    assert!(FrameRestorationType::from_lr_type(4).is_err());
+
+    assert_eq!(ReferenceMode::from(false), ReferenceMode::SingleReference);
+    assert_eq!(ReferenceMode::from(true), ReferenceMode::ReferenceSelect);
+}
+
+/// `SEQUENCE_HEADER` with enable_superres set.
+const SUPERRES_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xee, 0x01,
+];
+
+/// A single tile key frame with a superres denominator of 12 and
+/// base_q_idx 0, with reduced_tx_set set after the restoration types.
+const SUPERRES_FRAME: [u8; 9] = [0x32, 0x07, 0x10, 0x02, 0xd8, 0x00, 0x00, 0x20, 0x00];
+
+#[test]
+fn mi_dimensions_with_superres() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&SUPERRES_SEQUENCE_HEADER))
+        .unwrap();
+    let obu = parser.parse(&mut Buffer::new(&SUPERRES_FRAME)).unwrap();
+
+    // The frame is coded 1920 * 8 / 12 pixels wide and upscaled to 1920.
+    let ctx = parser.context();
+    assert_eq!(ctx.superres_denom(), 12);
+    assert_eq!(ctx.dimensions(), (1920, 1080));
+    assert_eq!(ctx.frame_dimensions(), (1280, 1080));
+    assert_eq!(ctx.mi_dimensions(), (320, 270));
+    assert_eq!(ctx.tile_info().mi_col_starts, [0, 320]);
+
+    // Not all lossless, the upscaling is not.
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert!(header.coded_lossless);
+    assert!(!header.all_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+/// `SUPERRES_FRAME` with a coded_denom of 0.
+const SUPERRES_9_FRAME: [u8; 9] = [0x32, 0x07, 0x10, 0x02, 0x18, 0x00, 0x00, 0x20, 0x00];
+
+#[test]
+fn smallest_superres_denominator() {
+    // Without enable_superres use_superres is not coded.
+    let mut parser = parser(ParseMode::Strict);
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    assert_eq!(parser.context().superres_denom(), 8);
+
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&SUPERRES_SEQUENCE_HEADER))
+        .unwrap();
+    parser.parse(&mut Buffer::new(&SUPERRES_9_FRAME)).unwrap();
+
+    // SUPERRES_DENOM_MIN, (1920 * 8 + 9 / 2) / 9 pixels wide.
+    let ctx = parser.context();
+    assert_eq!(ctx.superres_denom(), 9);
+    assert_eq!(ctx.frame_dimensions(), (1707, 1080));
+    assert_eq!(ctx.dimensions(), (1920, 1080));
+    assert_eq!(ctx.mi_dimensions(), (428, 270));
+}
+
+/// A frame header OBU of a single tile lossless key frame.
+const SINGLE_TILE_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0x80, 0x00, 0x40];
+
+#[test]
+fn single_tile_group_has_no_tile_range() {
+    let mut parser = parser(ParseMode::Strict);
+    parser
+        .parse(&mut Buffer::new(&SINGLE_TILE_FRAME_HEADER))
+        .unwrap();
+    assert_eq!(parser.context().tile_info().num_tiles(), 1);
+
+    // The first bit of the tile data would be tile_start_and_end_present_flag
+    // if it was read.
+    let tile_group = [0x22, 0x03, 0xaa, 0xbb, 0xcc];
+    let obu = parser.parse(&mut Buffer::new(&tile_group)).unwrap();
+    let tile_group = obu.as_tile_group().unwrap();
+
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (0, 0));
+    assert_eq!(tile_group.tiles, [TileData { offset: 2, len: 3 }]);
+    assert!(tile_group.frame_tiles_complete);
+}
+
+/// A 1920x1080 sequence header with a decoder model and two operating points,
+/// the first one has the temporal layers 0 and 1, the second one only the
+/// temporal layer 0. buffer_removal_time is 8 bits.
+const DECODER_MODEL_SEQUENCE_HEADER: [u8; 38] = [
+    0x0a, 0x24, 0x04, 0x00, 0x00, 0x0f, 0xa4, 0x00, 0x03, 0xa9, 0x81, 0x78, 0x00, 0x00, 0x1f, 0x41,
+    0xce, 0x08, 0x81, 0xa1, 0x00, 0x64, 0x00, 0xc8, 0x08, 0x0a, 0x10, 0x06, 0x40, 0x0c, 0x85, 0x57,
+    0x7f, 0x86, 0xe0, 0x0d, 0x80, 0x40,
+];
+
+/// A key frame header OBU of temporal layer 0, with the buffer removal times
+/// 0x22 and 0x33.
+const BASE_LAYER_FRAME_HEADER: [u8; 10] =
+    [0x1e, 0x00, 0x07, 0x11, 0x11, 0x22, 0x33, 0x60, 0x00, 0x10];
+
+/// A key frame header OBU of temporal layer 1, with the buffer removal time
+/// 0x44 of the first operating point only.
+const ENHANCEMENT_LAYER_FRAME_HEADER: [u8; 9] =
+    [0x1e, 0x20, 0x06, 0x11, 0x11, 0x44, 0x60, 0x00, 0x10];
+
+#[test]
+fn buffer_removal_time_of_the_operating_points_of_the_layer() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&DECODER_MODEL_SEQUENCE_HEADER))
+        .unwrap();
+
+    let obu = parser
+        .parse(&mut Buffer::new(&BASE_LAYER_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.buffer_removal_times, [Some(0x22), Some(0x33)]);
+
+    parser.parse(&mut Buffer::new(&[0x12, 0x00])).unwrap();
+
+    // The second operating point doesn't contain temporal layer 1.
+    let obu = parser
+        .parse(&mut Buffer::new(&ENHANCEMENT_LAYER_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.buffer_removal_times, [Some(0x44), None]);
+}
+
+#[test]
+fn obu_accessors() {
+    // Which of as_sequence_header, as_frame, as_frame_header, as_tile_group,
+    // as_metadata, is_temporal_delimiter and is_drop match.
+    let accessors = |obu: &Obu| {
+        [
+            obu.as_sequence_header().is_some(),
+            obu.as_frame().is_some(),
+            obu.as_frame_header().is_some(),
+            obu.as_tile_group().is_some(),
+            obu.as_metadata().is_some(),
+            obu.is_temporal_delimiter(),
+            obu.is_drop(),
+        ]
+    };
+
+    // The tile group of tile 0 of `FRAME_HEADER`, HDR CLL metadata and
+    // padding.
+    let tile_group = [0x22, 0x03, 0x80, 0xaa, 0xbb];
+    let metadata = [0x2a, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80];
+    let padding = [0x7a, 0x01, 0x00];
+
+    let mut parser = ObuParser::default();
+    for (i, bytes) in [
+        &SEQUENCE_HEADER[..],
+        &[0x12, 0x00],
+        &FRAME,
+        &[0x12, 0x00],
+        &FRAME_HEADER,
+        &tile_group,
+        &metadata,
+        &padding,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let obu = parser.parse(&mut Buffer::new(bytes)).unwrap();
+        let expected = [0, 5, 1, 5, 2, 3, 4, 6][i];
+
+        let matched = accessors(&obu);
+        for (accessor, matched) in matched.into_iter().enumerate() {
+            assert_eq!(
+                matched,
+                accessor == expected,
+                "obu {i}, accessor {accessor}"
+            );
+        }
+    }
+}
+
+#[test]
+fn delta_q() {
+    // 1 1111011, 0, 1 0111111
+    let mut buf = Buffer::new(&[0xfb, 0x5f, 0x80]);
+
+    assert_eq!(read_delta_q(&mut buf), -5);
+    assert_eq!(read_delta_q(&mut buf), 0);
+    assert_eq!(read_delta_q(&mut buf), 63);
+    assert_eq!(buf.get_position(), 17);
+}
+
+#[test]
+fn frame_obu_tile_group_size() {
+    // FRAME followed by a temporal delimiter, the last tile must stop at
+    // obu_size.
+    let bytes = [&FRAME[..], &[0x12, 0x00]].concat();
+
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let mut buf = Buffer::new(&bytes);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 2);
+
+    // The frame header takes 5 of the 19 bytes, the tile group the other 14:
+    // tile_start_and_end_present_flag, then 3 tiles prefixed with a 2 bytes
+    // size and the last tile.
+    let tile_group = &obu.as_frame().unwrap().tile_group;
+    assert_eq!(
+        tile_group.tiles,
+        [
+            TileData { offset: 10, len: 2 },
+            TileData { offset: 14, len: 2 },
+            TileData { offset: 18, len: 2 },
+            TileData { offset: 20, len: 1 },
+        ]
+    );
+    assert_eq!(tile_group.tile_data(&bytes).len(), 14 - 1 - 2);
+    assert!(tile_group.frame_tiles_complete);
+}
+
+#[test]
+fn shown_key_frame_is_not_showable() {
+    // showable_frame is 0 for a shown key frame.
+    let mut strict = parser(ParseMode::Strict);
+    let obu = strict.parse(&mut Buffer::new(&FRAME)).unwrap();
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert!(header.show_frame && !header.showable_frame);
+
+    strict.parse(&mut Buffer::new(&[0x12, 0x00])).unwrap();
+    assert_eq!(
+        strict
+            .parse(&mut Buffer::new(&SHOW_EXISTING_FRAME_HEADER))
+            .unwrap_err(),
+        ObuError::FrameNotShowable
+    );
+
+    let mut lenient = parser(ParseMode::Lenient);
+    lenient.parse(&mut Buffer::new(&FRAME)).unwrap();
+    lenient.parse(&mut Buffer::new(&[0x12, 0x00])).unwrap();
+    let obu = lenient
+        .parse(&mut Buffer::new(&SHOW_EXISTING_FRAME_HEADER))
+        .unwrap();
+    assert_eq!(
+        obu.as_frame_header().unwrap().frame_type(),
+        FrameType::KeyFrame
+    );
+}
+
+#[test]
+fn two_tile_groups_cover_the_frame() {
+    // The tiles 0 and 1 of `FRAME_HEADER`, `TILE_GROUP` holds the other two.
+    let first_tile_group = [0x22, 0x06, 0x88, 0x01, 0x00, 0xaa, 0xbb, 0xcc];
+
+    let mut parser = parser(ParseMode::Strict);
+    parser.parse(&mut Buffer::new(&FRAME_HEADER)).unwrap();
+
+    // The tile groups must follow each other.
+    assert_eq!(
+        parser.parse(&mut Buffer::new(&TILE_GROUP)).unwrap_err(),
+        ObuError::InvalidTileRange
+    );
+
+    let obu = parser.parse(&mut Buffer::new(&first_tile_group)).unwrap();
+    let tile_group = obu.as_tile_group().unwrap();
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (0, 1));
+    assert!(!tile_group.frame_tiles_complete);
+
+    let obu = parser.parse(&mut Buffer::new(&TILE_GROUP)).unwrap();
+    let tile_group = obu.as_tile_group().unwrap();
+    assert_eq!((tile_group.tg_start, tile_group.tg_end), (2, 3));
+    assert!(tile_group.frame_tiles_complete);
+
+    // NumTiles tiles were received, the frame is over.
+    assert_eq!(
+        parser.parse(&mut Buffer::new(&TILE_GROUP)).unwrap_err(),
+        ObuError::NotFoundFrameHeader
+    );
+}
+
+/// A frame header OBU of a lossless key frame in 4x2 uniform tiles, with
+/// tile_size_bytes_minus_1 = 3.
+const FOUR_BY_TWO_TILES_FRAME_HEADER: [u8; 8] = [0x1a, 0x06, 0x10, 0x00, 0xe8, 0x60, 0x00, 0x40];
+
+#[test]
+fn four_by_two_tiles() {
+    let mut parser = parser(ParseMode::Strict);
+    let obu = parser
+        .parse(&mut Buffer::new(&FOUR_BY_TWO_TILES_FRAME_HEADER))
+        .unwrap();
+    let frame_header = obu.as_frame_header().unwrap();
+    assert_eq!(frame_header.num_tiles(), 8);
+    assert_eq!(frame_header.tile_cols(), 4);
+    assert_eq!(frame_header.tile_rows(), 2);
+
+    // Tiles of 8x9 superblocks, the last ones are smaller.
+    let tile_info = frame_header.tile_info();
+    assert_eq!(tile_info.mi_col_starts, [0, 128, 256, 384, 480]);
+    assert_eq!(tile_info.mi_row_starts, [0, 144, 270]);
+    assert_eq!(tile_info.tile_size_bytes, 4);
+    assert_eq!(parser.context().tile_info().num_tiles(), 8);
+}
+
+/// A frame header OBU of a lossless key frame with every increment_tile_cols_log2 set.
+const MAX_TILE_COLS_FRAME_HEADER: [u8; 8] = [0x1a, 0x06, 0x10, 0x00, 0xfc, 0x00, 0x00, 0x18];
+
+#[test]
+fn max_tile_cols() {
+    // 30 superblock columns give maxLog2TileCols = tile_log2(1, 30) = 5, so
+    // only five increments are read before the tile rows.
+    let mut parser = parser(ParseMode::Strict);
+    let obu = parser
+        .parse(&mut Buffer::new(&MAX_TILE_COLS_FRAME_HEADER))
+        .unwrap();
+    let frame_header = obu.as_frame_header().unwrap();
+    let tile_info = frame_header.tile_info();
+    assert_eq!(tile_info.tile_cols_log2, 5);
+    assert_eq!(tile_info.tile_rows_log2, 0);
+
+    // Tiles of one superblock, the count is capped by the frame width.
+    assert_eq!(frame_header.tile_cols(), 30);
+    assert_eq!(frame_header.tile_rows(), 1);
+    assert_eq!(tile_info.mi_col_starts.len(), 31);
+    assert_eq!(tile_info.mi_col_starts[1], 16);
+    assert_eq!(tile_info.mi_col_starts[30], 480);
+}
+
+/// `SEQUENCE_HEADER` with use_128x128_superblock set.
+const SB_128_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x74, 0x09, 0xe6, 0x01,
+];
+
+/// A frame header OBU of a lossless key frame in 2x2 uniform tiles.
+const TWO_BY_TWO_TILES_FRAME_HEADER: [u8; 7] = [0x1a, 0x05, 0x10, 0x00, 0xd0, 0x00, 0x03];
+
+#[test]
+fn superblock_size() {
+    let mut parser = ObuParser::default();
+    let obu = parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    assert_eq!(obu.as_sequence_header().unwrap().superblock_size(), 64);
+
+    // Tiles of 15x9 superblocks of 64x64.
+    let obu = parser
+        .parse(&mut Buffer::new(&TWO_BY_TWO_TILES_FRAME_HEADER))
+        .unwrap();
+    let tile_info = obu.as_frame_header().unwrap().tile_info();
+    assert_eq!(tile_info.mi_col_starts, [0, 240, 480]);
+    assert_eq!(tile_info.mi_row_starts, [0, 144, 270]);
+
+    let mut parser = ObuParser::default();
+    let obu = parser
+        .parse(&mut Buffer::new(&SB_128_SEQUENCE_HEADER))
+        .unwrap();
+    assert_eq!(obu.as_sequence_header().unwrap().superblock_size(), 128);
+
+    // Tiles of 8x5 superblocks of 128x128.
+    let obu = parser
+        .parse(&mut Buffer::new(&TWO_BY_TWO_TILES_FRAME_HEADER))
+        .unwrap();
+    let tile_info = obu.as_frame_header().unwrap().tile_info();
+    assert_eq!(tile_info.mi_col_starts, [0, 256, 480]);
+    assert_eq!(tile_info.mi_row_starts, [0, 160, 270]);
+}
+
+/// `SEQUENCE_HEADER` with film_grain_params_present set.
+const FILM_GRAIN_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe6, 0x03,
+];
+
+/// `FRAME` with film grain parameters, grain_seed is 0xbeef.
+const FILM_GRAIN_KEY_FRAME: [u8; 42] = [
+    0x32, 0x28, 0x10, 0x00, 0xd0, 0x80, 0x03, 0xbe, 0xef, 0x10, 0xa1, 0x40, 0x8f, 0x14, 0x02, 0xb2,
+    0x32, 0xb3, 0x33, 0xe4, 0x64, 0xe5, 0x65, 0xe6, 0x58, 0x08, 0x14, 0xb2, 0x00, 0x01, 0x00, 0xaa,
+    0xbb, 0x01, 0x00, 0xaa, 0xbb, 0x01, 0x00, 0xaa, 0xbb, 0xcc,
+];
+
+/// `INTEGER_MV_INTER_FRAME` with grain_seed 0x1234, the other film grain
+/// parameters are loaded from film_grain_params_ref_idx 0.
+const FILM_GRAIN_REUSE_INTER_FRAME: [u8; 16] = [
+    0x32, 0x0e, 0x33, 0x01, 0xe0, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x02, 0x02, 0x24, 0x68, 0x00, 0x00,
+];
+
+#[test]
+fn film_grain_params_ref_idx() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&FILM_GRAIN_SEQUENCE_HEADER))
+        .unwrap();
+
+    let obu = parser
+        .parse(&mut Buffer::new(&FILM_GRAIN_KEY_FRAME))
+        .unwrap();
+    let key_frame_params = obu
+        .as_frame()
+        .unwrap()
+        .header
+        .uncompressed_header
+        .film_grain_params
+        .clone();
+    assert!(key_frame_params.apply_grain);
+    assert!(key_frame_params.update_grain);
+    assert_eq!(key_frame_params.grain_seed, 0xbeef);
+    assert_eq!(key_frame_params.point_y_value, [10]);
+    assert_eq!(
+        key_frame_params.ar_coeffs_cb_plus_128,
+        [200, 201, 202, 203, 204]
+    );
+    assert_eq!(key_frame_params.cb_offset, 300);
+
+    // Everything but the seed comes from the key frame in slot 0.
+    let mut buf = Buffer::new(&FILM_GRAIN_REUSE_INTER_FRAME);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::InterFrame);
+    assert_eq!(
+        header.film_grain_params,
+        FilmGrainParams {
+            grain_seed: 0x1234,
+            update_grain: false,
+            film_grain_params_ref_idx: Some(0),
+            ..key_frame_params
+        }
+    );
+}
+
+/// A 1920x1080 sequence header with timing info without an equal picture
+/// interval and a decoder model, frame_presentation_time is 8 bits.
+const PRESENTATION_TIME_SEQUENCE_HEADER: [u8; 28] = [
+    0x0a, 0x1a, 0x04, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x79, 0x48, 0x00, 0x00, 0x00, 0x0a,
+    0x4e, 0x00, 0x00, 0x20, 0xaa, 0xef, 0xf0, 0xdc, 0x02, 0x79, 0x80, 0x40,
+];
+
+/// A frame header OBU of a hidden, showable lossless key frame in slot 0.
+const HIDDEN_KEY_FRAME_HEADER: [u8; 9] = [0x1a, 0x07, 0x08, 0x05, 0x00, 0x9a, 0x10, 0x00, 0x60];
+
+/// A frame header OBU showing the frame in slot 0, with a
+/// frame_presentation_time of 0xab.
+const SHOW_EXISTING_PRESENTATION_TIME_FRAME_HEADER: [u8; 4] = [0x1a, 0x02, 0x8a, 0xb8];
+
+#[test]
+fn temporal_point_info_of_a_shown_existing_key_frame() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&PRESENTATION_TIME_SEQUENCE_HEADER))
+        .unwrap();
+
+    let obu = parser
+        .parse(&mut Buffer::new(&HIDDEN_KEY_FRAME_HEADER))
+        .unwrap();
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert!(!header.show_frame);
+    assert!(header.showable_frame);
+    assert!(header.temporal_point_info.is_none());
+    assert!(header.reduced_tx_set);
+
+    parser.parse(&mut Buffer::new(&[0x12, 0x00])).unwrap();
+
+    let mut buf = Buffer::new(&SHOW_EXISTING_PRESENTATION_TIME_FRAME_HEADER);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    assert_eq!(header.frame_to_show_map_idx, Some(0));
+    assert_eq!(header.frame_type, FrameType::KeyFrame);
+    assert_eq!(
+        header
+            .temporal_point_info
+            .as_ref()
+            .map(|it| it.frame_presentation_time),
+        Some(0xab)
+    );
+}
+
+/// A frame header OBU of a key frame with base_q_idx 100 and segmentation,
+/// ALT_Q is -20 in segment 0 and -256 in segment 3, SKIP is set in segment 1.
+const SEGMENTATION_FRAME_HEADER: [u8; 23] = [
+    0x1a, 0x15, 0x10, 0x00, 0xd0, 0xb2, 0x07, 0xec, 0x00, 0x04, 0x01, 0x80, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0,
+];
+
+#[test]
+fn segmentation_features() {
+    let mut parser = parser(ParseMode::Strict);
+
+    let mut buf = Buffer::new(&SEGMENTATION_FRAME_HEADER);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    let header = &obu.as_frame_header().unwrap().uncompressed_header;
+    let params = &header.segmentation_params;
+    assert!(params.segmentation_enabled);
+
+    // ALT_Q is signed on 1 + 8 bits and clamped to 255.
+    assert_eq!(params.feature_data[0][0], Some(-20));
+    assert_eq!(params.feature_data[3][0], Some(-255));
+    assert_eq!(params.qindex(100, 0), 80);
+    assert_eq!(params.qindex(100, 3), 0);
+
+    // SKIP has no value.
+    assert_eq!(params.feature_data[1][6], Some(0));
+    assert!(params.seg_id_pre_skip);
+    assert_eq!(params.last_active_seg_id, 3);
+
+    // The syntax after the segmentation params is in place.
+    assert!(!header.coded_lossless);
+    assert!(header.reduced_tx_set);
+}
+
+#[test]
+fn frames_parsed() {
+    let mut parser = parser(ParseMode::Strict);
+    for _ in 0..3 {
+        parser.parse(&mut Buffer::new(&[0x12, 0x00])).unwrap();
+        parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    }
+
+    assert_eq!(parser.frames_parsed(), 3);
+    assert_eq!(parser.context().frames_parsed(), 3);
+
+    // Showing an existing frame decodes no new frame.
+    let mut parser = ObuParser::default();
+    for bytes in [
+        &PRESENTATION_TIME_SEQUENCE_HEADER[..],
+        &HIDDEN_KEY_FRAME_HEADER,
+        &[0x12, 0x00],
+        &SHOW_EXISTING_PRESENTATION_TIME_FRAME_HEADER,
+    ] {
+        parser.parse(&mut Buffer::new(bytes)).unwrap();
+    }
+
+    assert_eq!(parser.frames_parsed(), 1);
+}
+
+#[test]
+fn display() {
+    let mut parser = parser(ParseMode::Strict);
+    let sequence_header = parser.sequence_header().unwrap();
+    assert_eq!(
+        sequence_header.to_string(),
+        "Main 1920x1080 8bit 4:2:0 level 4.0"
+    );
+
+    let obu = parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    let frame = obu.as_frame().unwrap();
+    assert_eq!(
+        frame.to_string(),
+        "Frame KEY_FRAME 1920x1080 base_q_idx=0 tiles=4 show=1"
+    );
+
+    let obu = parser
+        .parse(&mut Buffer::new(&INTEGER_MV_INTER_FRAME))
+        .unwrap();
+    let summary = obu.as_frame().unwrap().to_string();
+    assert!(summary.contains("INTER_FRAME"));
+    assert!(summary.contains("1920x1080"));
+}
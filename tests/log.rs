@@ -0,0 +1,64 @@
+use std::sync::Mutex;
+
+use av1_obu_parser::{buffer::Buffer, obu::ObuParser};
+use log::{Level, Log, Metadata, Record};
+
+mod common;
+
+use common::SEQUENCE_HEADER;
+
+/// Keeps every record of the parser.
+struct CapturingLogger(Mutex<Vec<(Level, String)>>);
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn trace_each_obu() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    // A temporal delimiter, the sequence header and an empty OBU of the
+    // reserved type 9.
+    let bytes = [&[0x12, 0x00][..], &SEQUENCE_HEADER, &[0x4a, 0x00]].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    while buf.remaining_bytes() > 0 {
+        parser.parse(&mut buf).unwrap();
+    }
+
+    assert_eq!(
+        *LOGGER.0.lock().unwrap(),
+        [
+            (
+                Level::Trace,
+                "obu: type=TemporalDelimiter, size=0, temporal_id=None, spatial_id=None"
+                    .to_string()
+            ),
+            (
+                Level::Trace,
+                "obu: type=SequenceHeader, size=11, temporal_id=None, spatial_id=None".to_string()
+            ),
+            (
+                Level::Trace,
+                "obu: type=Reserved(9), size=0, temporal_id=None, spatial_id=None".to_string()
+            ),
+            (Level::Warn, "obu dropped, reserved type: 9".to_string()),
+        ]
+    );
+}
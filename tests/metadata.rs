@@ -0,0 +1,192 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{
+        ObuError, ObuParser, ObuUnknownError, ParseMode,
+        metadata::{Metadata, MetadataType, ScalabilityModeIdc, ScalabilityStructure},
+    },
+};
+
+fn parse(bytes: &[u8]) -> Metadata {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(bytes);
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    obu.as_metadata().unwrap().clone()
+}
+
+#[test]
+fn hdr_cll_round_trip() {
+    let bytes = Metadata::hdr_cll(1000, 400).to_obu();
+    assert_eq!(bytes, [0x2a, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80]);
+
+    assert!(matches!(
+        parse(&bytes),
+        Metadata::HdrCll {
+            max_cll: 1000,
+            max_fall: 400
+        }
+    ));
+}
+
+#[test]
+fn mastering_display_round_trip() {
+    // BT.2020 primaries, D65 white point, 1000 and 0.005 cd/m2.
+    let primaries = [(34000, 16000), (13250, 34500), (7500, 3000)];
+    let bytes = Metadata::mastering_display(primaries, (15635, 16450), 1000 << 8, 82).to_obu();
+
+    let Metadata::HdrMdcv {
+        primary_chromaticity_x,
+        primary_chromaticity_y,
+        white_point_chromaticity_x,
+        white_point_chromaticity_y,
+        luminance_max,
+        luminance_min,
+    } = parse(&bytes)
+    else {
+        panic!("not mastering display metadata");
+    };
+
+    assert_eq!(primary_chromaticity_x, [34000, 13250, 7500]);
+    assert_eq!(primary_chromaticity_y, [16000, 34500, 3000]);
+    assert_eq!(
+        (white_point_chromaticity_x, white_point_chromaticity_y),
+        (15635, 16450)
+    );
+    assert_eq!((luminance_max, luminance_min), (1000 << 8, 82));
+}
+
+#[test]
+fn scalability_structure_round_trip() {
+    // Three spatial layers without dimensions or descriptions.
+    let metadata = Metadata::Scalability {
+        mode_idc: ScalabilityModeIdc::SS,
+        scalability_structure: Some(ScalabilityStructure {
+            spatial_layers_cnt: 3,
+            spatial_layers: None,
+            spatial_layer_descriptions: None,
+            temporal_groups: None,
+        }),
+    };
+
+    let Metadata::Scalability {
+        mode_idc,
+        scalability_structure: Some(scalability_structure),
+    } = parse(&metadata.to_obu())
+    else {
+        panic!("not a scalability structure");
+    };
+    assert_eq!(mode_idc, ScalabilityModeIdc::SS);
+    assert_eq!(scalability_structure.spatial_layers_cnt, 3);
+    assert!(scalability_structure.spatial_layers.is_none());
+    assert!(scalability_structure.spatial_layer_descriptions.is_none());
+    assert!(scalability_structure.temporal_groups.is_none());
+}
+
+fn timecode(time_offset_length: usize, time_offset_value: Option<u32>) -> Metadata {
+    Metadata::Timecode {
+        counting_type: 0,
+        full_timestamp: false,
+        discontinuity: false,
+        cnt_dropped: false,
+        n_frames: 10,
+        seconds_value: Some(1),
+        minutes_value: None,
+        hours_value: None,
+        time_offset_length,
+        time_offset_value,
+    }
+}
+
+#[test]
+fn timecode_round_trip() {
+    let Metadata::Timecode {
+        seconds_value,
+        time_offset_length,
+        time_offset_value,
+        ..
+    } = parse(&timecode(8, Some(0xab)).to_obu())
+    else {
+        panic!("not a timecode");
+    };
+    assert_eq!(seconds_value, Some(1));
+    assert_eq!((time_offset_length, time_offset_value), (8, Some(0xab)));
+
+    // Without a time offset the value is not written.
+    let Metadata::Timecode {
+        time_offset_length,
+        time_offset_value,
+        ..
+    } = parse(&timecode(0, Some(0xab)).to_obu())
+    else {
+        panic!("not a timecode");
+    };
+    assert_eq!((time_offset_length, time_offset_value), (0, None));
+}
+
+#[test]
+fn metadata_types() {
+    for (value, kind) in [
+        (0, MetadataType::Reserved(0)),
+        (1, MetadataType::HdrCll),
+        (2, MetadataType::HdrMdcv),
+        (3, MetadataType::Scalability),
+        (4, MetadataType::ItutT35),
+        (5, MetadataType::Timecode),
+        (6, MetadataType::Unregistered(6)),
+        (31, MetadataType::Unregistered(31)),
+        (32, MetadataType::Reserved(32)),
+    ] {
+        assert_eq!(MetadataType::from(value), kind);
+        assert_eq!(u32::from(kind), value);
+    }
+
+    let defined = [
+        Metadata::hdr_cll(1000, 400),
+        Metadata::mastering_display([(0, 0); 3], (0, 0), 0, 0),
+        Metadata::Scalability {
+            mode_idc: ScalabilityModeIdc::L1T2,
+            scalability_structure: None,
+        },
+        Metadata::ItutT35 {
+            country_code: 0xb5,
+            country_code_extension_byte: None,
+            payload: vec![0x00, 0x3c, 0x00, 0x01],
+        },
+        Metadata::Timecode {
+            counting_type: 0,
+            full_timestamp: true,
+            discontinuity: false,
+            cnt_dropped: false,
+            n_frames: 10,
+            seconds_value: Some(1),
+            minutes_value: Some(2),
+            hours_value: Some(3),
+            time_offset_length: 0,
+            time_offset_value: None,
+        },
+    ];
+
+    for metadata in defined {
+        assert_eq!(parse(&metadata.to_obu()).kind(), metadata.kind());
+    }
+
+    // Unregistered user private metadata, the payload is not parsed.
+    let metadata = parse(&[0x2a, 0x03, 0x1f, 0xaa, 0x80]);
+    assert!(matches!(metadata, Metadata::UnregisteredUserPrivate(31)));
+
+    // metadata_type 1000 is reserved.
+    let bytes = [0x2a, 0x03, 0xe8, 0x07, 0x80];
+    let mut parser = ObuParser::default();
+    assert_eq!(
+        parser.parse(&mut Buffer::new(&bytes)).unwrap_err(),
+        ObuError::Unknown(ObuUnknownError::MetadataType)
+    );
+
+    parser.set_mode(ParseMode::Lenient);
+    let obu = parser.parse(&mut Buffer::new(&bytes)).unwrap();
+    assert_eq!(
+        obu.as_metadata().unwrap().kind(),
+        MetadataType::Reserved(1000)
+    );
+}
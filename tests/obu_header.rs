@@ -0,0 +1,180 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{Obu, ObuError, ObuParser, ObuType, ParseMode, parse_obu_header},
+};
+
+/// HDR CLL metadata with an extension header, temporal_id and spatial_id are
+/// 0 and extension_header_reserved_3bits is 5.
+const METADATA: [u8; 9] = [0x2e, 0x05, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80];
+
+const TEMPORAL_DELIMITER: [u8; 2] = [0x12, 0x00];
+
+#[test]
+fn extension_reserved_bits_strict() {
+    let bytes = [&METADATA[..], &TEMPORAL_DELIMITER].concat();
+    let mut buf = Buffer::new(&bytes);
+
+    let mut parser = ObuParser::default();
+    assert!(matches!(
+        parser.parse(&mut buf),
+        Err(ObuError::InvalidReservedBits)
+    ));
+
+    // The OBU is skipped.
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+}
+
+#[test]
+fn extension_reserved_bits_lenient() {
+    let mut parser = ObuParser::default();
+    parser.set_mode(ParseMode::Lenient);
+
+    let obu = parser.parse(&mut Buffer::new(&METADATA)).unwrap();
+    assert!(matches!(obu, Obu::Metadata(_)));
+}
+
+#[test]
+fn parse_obu_header_stops_at_the_payload() {
+    let mut buf = Buffer::new(&METADATA);
+    let (header, size) = parse_obu_header(&mut buf).unwrap();
+
+    assert_eq!(header.r#type, ObuType::Metadata);
+    assert!(header.has_size);
+    assert_eq!(header.extension.map(|it| it.temporal_id), Some(0));
+    assert_eq!(
+        header
+            .extension
+            .map(|it| it.extension_header_reserved_3bits),
+        Some(5)
+    );
+    assert_eq!(size, Some(METADATA.len() - 3));
+    assert_eq!(buf.get_position(), 3 * 8);
+    assert_eq!(buf.remaining_bytes(), size.unwrap());
+}
+
+#[test]
+fn parse_obu_header_without_the_extension_byte() {
+    // A temporal delimiter with obu_extension_flag set, cut after the header.
+    let mut buf = Buffer::new(&[0x16]);
+    assert!(matches!(
+        parse_obu_header(&mut buf),
+        Err(ObuError::UnexpectedEof)
+    ));
+
+    let mut parser = ObuParser::default();
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&[0x16])),
+        Err(ObuError::UnexpectedEof)
+    ));
+}
+
+/// Sequence header with two spatial and two temporal layers, operating point
+/// 0 has every layer (idc 0x303) and operating point 1 only the base layers
+/// (idc 0x101).
+const L2T2_SEQUENCE_HEADER: [u8; 15] = [
+    0x0a, 0x0d, 0x00, 0x13, 0x03, 0x40, 0x40, 0x50, 0xaa, 0xef, 0xf0, 0xdc, 0x01, 0xb0, 0x08,
+];
+
+/// HDR CLL metadata with an extension header for `temporal_id` and
+/// `spatial_id`.
+fn layer_metadata(temporal_id: u8, spatial_id: u8) -> [u8; 9] {
+    let extension = (temporal_id << 5) | (spatial_id << 3);
+    [0x2e, extension, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80]
+}
+
+#[test]
+fn operating_point_idc_override() {
+    let mut parser = ObuParser::default();
+
+    // Spatial layer 0 with both temporal layers, which none of the signaled
+    // operating points selects.
+    parser.set_operating_point_idc(0x0103);
+    parser
+        .parse(&mut Buffer::new(&L2T2_SEQUENCE_HEADER))
+        .unwrap();
+
+    for (temporal_id, spatial_id, dropped) in
+        [(0, 0, false), (1, 0, false), (0, 1, true), (1, 1, true)]
+    {
+        let obu = parser
+            .parse(&mut Buffer::new(&layer_metadata(temporal_id, spatial_id)))
+            .unwrap();
+        assert_eq!(obu.is_drop(), dropped);
+        assert_eq!(matches!(obu, Obu::Metadata(_)), !dropped);
+    }
+
+    // 0 keeps every layer.
+    parser.set_operating_point_idc(0);
+    let obu = parser
+        .parse(&mut Buffer::new(&layer_metadata(1, 1)))
+        .unwrap();
+    assert!(matches!(obu, Obu::Metadata(_)));
+}
+
+/// A sequence header with three operating points: temporal and spatial layer
+/// 0 (idc 0x101), both temporal and spatial layers (idc 0x303) and both
+/// temporal layers of spatial layer 0 (idc 0x103).
+const THREE_OPERATING_POINTS_SEQUENCE_HEADER: [u8; 17] = [
+    0x0a, 0x0f, 0x00, 0x21, 0x01, 0x40, 0xc0, 0xd0, 0x10, 0x34, 0x2a, 0xbb, 0xfc, 0x37, 0x00, 0x6c,
+    0x02,
+];
+
+#[test]
+fn select_highest_operating_point() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&THREE_OPERATING_POINTS_SEQUENCE_HEADER))
+        .unwrap();
+    assert_eq!(parser.context().operating_point(), (0, 0x101));
+    assert!(
+        parser
+            .parse(&mut Buffer::new(&layer_metadata(1, 1)))
+            .unwrap()
+            .is_drop()
+    );
+
+    // The operating point with all the layers, no OBU is dropped.
+    parser.select_highest_operating_point();
+    assert_eq!(parser.context().operating_point(), (1, 0x303));
+    for (temporal_id, spatial_id) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let obu = parser
+            .parse(&mut Buffer::new(&layer_metadata(temporal_id, spatial_id)))
+            .unwrap();
+        assert!(matches!(obu, Obu::Metadata(_)));
+    }
+
+    // Still the choice when the sequence header is repeated.
+    parser
+        .parse(&mut Buffer::new(&THREE_OPERATING_POINTS_SEQUENCE_HEADER))
+        .unwrap();
+    assert_eq!(parser.context().operating_point(), (1, 0x303));
+}
+
+#[test]
+fn spatial_id_beyond_the_spatial_layers() {
+    let mut parser = ObuParser::default();
+    parser
+        .parse(&mut Buffer::new(&L2T2_SEQUENCE_HEADER))
+        .unwrap();
+
+    // Only the spatial layers 0 and 1 are declared.
+    let bytes = [&layer_metadata(0, 2)[..], &TEMPORAL_DELIMITER].concat();
+    let mut buf = Buffer::new(&bytes);
+    assert!(matches!(
+        parser.parse(&mut buf),
+        Err(ObuError::InvalidLayerId)
+    ));
+
+    // The OBU is skipped.
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+
+    // The layer is clamped to the highest one.
+    parser.set_mode(ParseMode::Lenient);
+    let obu = parser
+        .parse(&mut Buffer::new(&layer_metadata(1, 3)))
+        .unwrap();
+    assert!(matches!(obu, Obu::Metadata(_)));
+
+    let extension = parser.context().obu_header_extension().unwrap();
+    assert_eq!((extension.temporal_id, extension.spatial_id), (1, 1));
+}
@@ -0,0 +1,406 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{
+        ObuError, ObuParser, ObuUnknownError, ParseMode,
+        frame_header::FrameType,
+        level::Level,
+        sequence_header::{
+            ChromaSamplePosition, ChromaSubsampling, FrameIdNumbersPresent, MatrixCoefficients,
+            SequenceHeader, SequenceProfile,
+        },
+    },
+};
+
+mod common;
+
+use common::{FRAME, FRAME_ID_SEQUENCE_HEADER, SEQUENCE_HEADER};
+
+/// `SEQUENCE_HEADER` with timing info, 60000/1001 frames per second with an
+/// equal picture interval, and no decoder model info.
+const TIMING_INFO_SEQUENCE_HEADER: [u8; 22] = [
+    0x0a, 0x14, 0x04, 0x00, 0x00, 0x0f, 0xa4, 0x00, 0x03, 0xa9, 0x83, 0x00, 0x00, 0x08, 0x55, 0x77,
+    0xf8, 0x6e, 0x01, 0x3c, 0xc0, 0x20,
+];
+
+#[test]
+fn sequence_header_try_from_bytes() {
+    let sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+
+    assert_eq!(
+        sequence_header.to_string(),
+        "Main 1920x1080 8bit 4:2:0 level 4.0"
+    );
+    assert!(sequence_header.enable_order_hint);
+    assert!(sequence_header.enable_cdef);
+}
+
+#[test]
+fn sequence_header_try_from_other_obu() {
+    // temporal delimiter
+    let bytes = [0x12, 0x00];
+
+    assert!(matches!(
+        SequenceHeader::try_from(&bytes[..]),
+        Err(ObuError::NotFoundSequenceHeader)
+    ));
+}
+
+#[test]
+fn sequence_header_max_frame_size() {
+    let mut sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(sequence_header.max_frame_width, 1919);
+    assert_eq!(sequence_header.max_frame_height, 1079);
+    assert_eq!(sequence_header.max_width(), 1920);
+    assert_eq!(sequence_header.max_height(), 1080);
+
+    // 16 bits max_frame_width_minus_1 and max_frame_height_minus_1.
+    sequence_header.max_frame_width = u16::MAX;
+    sequence_header.max_frame_height = u16::MAX;
+    assert_eq!(sequence_header.max_width(), 65536);
+    assert_eq!(sequence_header.max_height(), 65536);
+}
+
+#[test]
+fn sequence_header_frame_id_length() {
+    let sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(sequence_header.frame_id_length(), 0);
+
+    let mut sequence_header = SequenceHeader::try_from(&FRAME_ID_SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(sequence_header.frame_id_length(), 8);
+
+    // delta_frame_id_length_minus_2 0 and additional_frame_id_length_minus_1
+    // 6.
+    sequence_header.frame_id_numbers_present = Some(FrameIdNumbersPresent {
+        delta_frame_id_length: 2,
+        additional_frame_id_length: 7,
+    });
+    assert_eq!(sequence_header.frame_id_length(), 9);
+}
+
+#[test]
+fn sequence_header_subsampling() {
+    let mut sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(sequence_header.subsampling(), ChromaSubsampling::Yuv420);
+
+    for (mono_chrome, subsampling_x, subsampling_y, subsampling, name) in [
+        (false, true, false, ChromaSubsampling::Yuv422, "4:2:2"),
+        (false, false, false, ChromaSubsampling::Yuv444, "4:4:4"),
+        (true, true, true, ChromaSubsampling::Monochrome, "4:0:0"),
+        (false, true, true, ChromaSubsampling::Yuv420, "4:2:0"),
+    ] {
+        let color_config = &mut sequence_header.color_config;
+        color_config.mono_chrome = mono_chrome;
+        color_config.subsampling_x = subsampling_x;
+        color_config.subsampling_y = subsampling_y;
+
+        assert_eq!(sequence_header.subsampling(), subsampling);
+        assert_eq!(subsampling.to_string(), name);
+    }
+}
+
+/// `SEQUENCE_HEADER` with BT.709 primaries and transfer characteristics, and
+/// the identity matrix.
+const IDENTITY_MATRIX_SEQUENCE_HEADER: [u8; 16] = [
+    0x0a, 0x0e, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe6, 0x40, 0x40, 0x40, 0x01,
+];
+
+#[test]
+fn identity_matrix_with_subsampling() {
+    // The main profile is always 4:2:0.
+    let mut parser = ObuParser::default();
+    assert_eq!(
+        parser
+            .parse(&mut Buffer::new(&IDENTITY_MATRIX_SEQUENCE_HEADER))
+            .unwrap_err(),
+        ObuError::Unknown(ObuUnknownError::MatrixCoefficients)
+    );
+
+    parser.set_mode(ParseMode::Lenient);
+    let obu = parser
+        .parse(&mut Buffer::new(&IDENTITY_MATRIX_SEQUENCE_HEADER))
+        .unwrap();
+    let sequence_header = obu.as_sequence_header().unwrap();
+    assert_eq!(
+        sequence_header.color_config.matrix_coefficients,
+        MatrixCoefficients::Identity
+    );
+    assert_eq!(sequence_header.subsampling(), ChromaSubsampling::Yuv420);
+}
+
+/// A high profile 1920x1080 8 bit sequence header with the sRGB color
+/// description, hence 4:4:4.
+const SRGB_HIGH_PROFILE_SEQUENCE_HEADER: [u8; 15] = [
+    0x0a, 0x0d, 0x20, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x06, 0xd0, 0x10, 0xd0, 0x02,
+];
+
+/// `SRGB_HIGH_PROFILE_SEQUENCE_HEADER` in the main profile.
+const SRGB_MAIN_PROFILE_SEQUENCE_HEADER: [u8; 15] = [
+    0x0a, 0x0d, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x06, 0xc8, 0x08, 0x68, 0x01,
+];
+
+#[test]
+fn profile_and_color_config() {
+    let mut parser = ObuParser::default();
+    let obu = parser
+        .parse(&mut Buffer::new(&SRGB_HIGH_PROFILE_SEQUENCE_HEADER))
+        .unwrap();
+    let sequence_header = obu.as_sequence_header().unwrap();
+    assert_eq!(sequence_header.seq_profile, SequenceProfile::High);
+    assert_eq!(sequence_header.subsampling(), ChromaSubsampling::Yuv444);
+
+    // The main profile can't carry 4:4:4.
+    let mut parser = ObuParser::default();
+    assert_eq!(
+        parser
+            .parse(&mut Buffer::new(&SRGB_MAIN_PROFILE_SEQUENCE_HEADER))
+            .unwrap_err(),
+        ObuError::Unknown(ObuUnknownError::Profile)
+    );
+
+    parser.set_mode(ParseMode::Lenient);
+    let obu = parser
+        .parse(&mut Buffer::new(&SRGB_MAIN_PROFILE_SEQUENCE_HEADER))
+        .unwrap();
+    let sequence_header = obu.as_sequence_header().unwrap();
+    assert_eq!(sequence_header.seq_profile, SequenceProfile::Main);
+    assert_eq!(sequence_header.subsampling(), ChromaSubsampling::Yuv444);
+}
+
+/// A 1920x1080 still picture sequence header with the reduced header,
+/// level 4.0.
+const REDUCED_STILL_PICTURE_SEQUENCE_HEADER: [u8; 9] =
+    [0x0a, 0x07, 0x1a, 0x2a, 0xbb, 0xfc, 0x37, 0x0c, 0x02];
+
+#[test]
+fn reduced_still_picture_header() {
+    let sequence_header =
+        SequenceHeader::try_from(&REDUCED_STILL_PICTURE_SEQUENCE_HEADER[..]).unwrap();
+    assert!(sequence_header.still_picture);
+    assert!(sequence_header.reduced_still_picture_header);
+    assert!(sequence_header.timing_info.is_none());
+    assert!(sequence_header.decoder_model_info.is_none());
+    assert!(!sequence_header.initial_display_delay_present_flag);
+
+    // A single operating point with every layer, only the level is coded.
+    assert_eq!(sequence_header.operating_points.len(), 1);
+    let operating_point = &sequence_header.operating_points[0];
+    assert_eq!(operating_point.idc, 0);
+    assert_eq!(operating_point.level_idx, 8);
+    assert!(!operating_point.tier);
+    assert!(operating_point.operating_parameters_info.is_none());
+
+    assert_eq!(sequence_header.max_width(), 1920);
+    assert!(sequence_header.frame_id_numbers_present.is_none());
+    assert!(!sequence_header.enable_order_hint);
+    assert!(sequence_header.enable_cdef);
+}
+
+#[test]
+fn sequence_header_fits_level() {
+    let mut sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert!(sequence_header.fits_level(Level::new(4, 0)));
+    assert!(!sequence_header.fits_level(Level::new(3, 1)));
+    assert!(sequence_header.fits_level(Level::MAX_PARAMETERS));
+
+    // A 3840x2160 stream at level 5.1.
+    sequence_header.max_frame_width = 3839;
+    sequence_header.max_frame_height = 2159;
+    sequence_header.operating_points[0].level_idx = Level::new(5, 1).seq_level_idx();
+    assert!(!sequence_header.fits_level(Level::new(4, 0)));
+    assert!(sequence_header.fits_level(Level::new(5, 1)));
+
+    // Labelled 4.0, the frame size is still too large.
+    sequence_header.operating_points[0].level_idx = Level::new(4, 0).seq_level_idx();
+    assert!(!sequence_header.fits_level(Level::new(4, 0)));
+    assert!(sequence_header.fits_level(Level::new(5, 1)));
+
+    // 1080p at 60000/1001 frames per second exceeds the display rate of 4.0.
+    let sequence_header = SequenceHeader::try_from(&TIMING_INFO_SEQUENCE_HEADER[..]).unwrap();
+    assert!(!sequence_header.fits_level(Level::new(4, 0)));
+    assert!(sequence_header.fits_level(Level::new(4, 1)));
+}
+
+/// `SEQUENCE_HEADER` as a monochrome, full range stream with film grain.
+const MONOCHROME_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe6, 0xb8,
+];
+
+#[test]
+fn monochrome_sequence_header() {
+    // The trailing bits are checked, nothing is read after color_range but
+    // film_grain_params_present.
+    let mut parser = ObuParser::default();
+    let obu = parser
+        .parse(&mut Buffer::new(&MONOCHROME_SEQUENCE_HEADER))
+        .unwrap();
+    let sequence_header = obu.as_sequence_header().unwrap();
+    assert!(sequence_header.monochrome());
+    assert!(sequence_header.film_grain_params_present);
+    assert_eq!(parser.context().num_planes(), 1);
+
+    let color_config = &sequence_header.color_config;
+    assert!(color_config.color_range);
+    assert!(color_config.subsampling_x && color_config.subsampling_y);
+    assert_eq!(
+        color_config.chroma_sample_position,
+        Some(ChromaSamplePosition::Unknown)
+    );
+    assert!(!color_config.separate_uv_delta_q);
+    assert_eq!(color_config.num_planes(), 1);
+    assert_eq!(sequence_header.subsampling(), ChromaSubsampling::Monochrome);
+}
+
+#[test]
+fn sequence_header_equality() {
+    // `SEQUENCE_HEADER` with high_bitdepth set.
+    let ten_bit = [
+        0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe7, 0x01,
+    ];
+
+    let sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(
+        sequence_header,
+        SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap()
+    );
+
+    let ten_bit = SequenceHeader::try_from(&ten_bit[..]).unwrap();
+    assert!(ten_bit.color_config.high_bitdepth);
+    assert_ne!(sequence_header, ten_bit);
+}
+
+/// A sequence header with initial display delays and two operating points,
+/// only the first one (idc 0x103) codes an initial_display_delay_minus_1, of
+/// 3.
+const INITIAL_DISPLAY_DELAY_SEQUENCE_HEADER: [u8; 16] = [
+    0x0a, 0x0e, 0x02, 0x11, 0x03, 0x42, 0x62, 0x02, 0x82, 0xab, 0xbf, 0xc3, 0x70, 0x06, 0xc0, 0x20,
+];
+
+#[test]
+fn initial_display_delay() {
+    // BUFFER_POOL_MAX_SIZE when it is not coded.
+    let sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert!(!sequence_header.initial_display_delay_present_flag);
+    assert_eq!(
+        sequence_header.operating_points[0].initial_display_delay,
+        10
+    );
+
+    let sequence_header =
+        SequenceHeader::try_from(&INITIAL_DISPLAY_DELAY_SEQUENCE_HEADER[..]).unwrap();
+    assert!(sequence_header.initial_display_delay_present_flag);
+    let delays = sequence_header
+        .operating_points
+        .iter()
+        .map(|it| (it.idc, it.initial_display_delay))
+        .collect::<Vec<_>>();
+    assert_eq!(delays, [(0x103, 4), (0x101, 10)]);
+}
+
+#[test]
+fn sequence_header_max_layers() {
+    let mut sequence_header = SequenceHeader::try_from(&SEQUENCE_HEADER[..]).unwrap();
+    assert_eq!(sequence_header.max_layers(), (1, 1));
+
+    // L2T2, every operating point drops a spatial and/or a temporal layer.
+    let operating_point = sequence_header.operating_points[0].clone();
+    sequence_header.operating_points = [0x0303, 0x0103, 0x0301, 0x0101]
+        .into_iter()
+        .map(|idc| {
+            let mut operating_point = operating_point.clone();
+            operating_point.idc = idc;
+            operating_point
+        })
+        .collect();
+
+    assert_eq!(sequence_header.max_layers(), (2, 2));
+
+    sequence_header.operating_points.truncate(1);
+    sequence_header.operating_points[0].idc = 0x0107;
+    assert_eq!(sequence_header.max_layers(), (3, 1));
+}
+
+#[test]
+fn timing_info_without_decoder_model() {
+    let mut parser = ObuParser::default();
+    let obu = parser
+        .parse(&mut Buffer::new(&TIMING_INFO_SEQUENCE_HEADER))
+        .unwrap();
+    let sequence_header = obu.as_sequence_header().unwrap();
+
+    assert!(sequence_header.timing_info_present_flag());
+    assert!(!sequence_header.decoder_model_info_present_flag());
+
+    let timing_info = sequence_header.timing_info.unwrap();
+    assert_eq!(timing_info.num_units_in_display_tick, 1001);
+    assert_eq!(timing_info.time_scale, 60000);
+    assert_eq!(
+        timing_info
+            .equal_picture_interval
+            .unwrap()
+            .num_ticks_per_picture,
+        1
+    );
+
+    // The operating points and everything after them are read in sync.
+    assert_eq!(
+        sequence_header.to_string(),
+        "Main 1920x1080 8bit 4:2:0 level 4.0"
+    );
+    assert!(sequence_header.enable_order_hint);
+    assert!(sequence_header.enable_cdef);
+
+    // Without a decoder model, frame headers code neither temporal_point_info
+    // nor buffer_removal_time.
+    let obu = parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    let header = &obu.as_frame().unwrap().header.uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::KeyFrame);
+    assert!(header.temporal_point_info.is_none());
+    assert!(header.buffer_removal_times.iter().all(Option::is_none));
+    assert!(header.coded_lossless);
+}
+
+#[test]
+fn sequence_header_trailing_bits() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    assert!(!parser.has_trailing_padding());
+
+    // The stop bit followed by a zero byte of padding.
+    let mut padded = SEQUENCE_HEADER.to_vec();
+    padded[1] += 1;
+    padded.push(0x00);
+    let mut buf = Buffer::new(&padded);
+    parser.parse(&mut buf).unwrap();
+    assert!(parser.has_trailing_padding());
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    // The stop bit is missing.
+    let mut missing = SEQUENCE_HEADER;
+    missing[12] = 0x00;
+    assert!(matches!(
+        parser.parse(&mut Buffer::new(&missing)),
+        Err(ObuError::InvalidTrailingBits)
+    ));
+
+    parser.set_mode(ParseMode::Lenient);
+    parser.parse(&mut Buffer::new(&missing)).unwrap();
+}
+
+#[test]
+fn sequence_header_ends_at_obu_size() {
+    let mut parser = ObuParser::default();
+
+    // The stop bit, a zero byte of padding and the next OBU.
+    let mut bytes = SEQUENCE_HEADER.to_vec();
+    bytes[1] += 1;
+    bytes.push(0x00);
+    bytes.extend_from_slice(&FRAME);
+
+    let mut buf = Buffer::new(&bytes);
+    parser.parse(&mut buf).unwrap();
+    assert_eq!(buf.get_position(), (SEQUENCE_HEADER.len() + 1) * 8);
+    assert_eq!(buf.remaining_bytes(), FRAME.len());
+
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(obu.as_frame().unwrap().frame_type(), FrameType::KeyFrame);
+}
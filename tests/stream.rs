@@ -0,0 +1,634 @@
+use av1_obu_parser::{
+    buffer::Buffer,
+    obu::{
+        Obu, ObuError, ObuParser, ObuType, ParseMode, annexb::BitstreamFormat,
+        frame_header::FrameType, streaming::StreamingObuParser,
+    },
+};
+
+mod common;
+
+use common::{FRAME, SEQUENCE_HEADER};
+
+/// `SEQUENCE_HEADER` with level 4.1 instead of 4.0.
+const OTHER_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x4a, 0xab, 0xbf, 0xc3, 0x70, 0x09, 0xe6, 0x01,
+];
+
+const TEMPORAL_DELIMITER: [u8; 2] = [0x12, 0x00];
+
+/// HDR CLL metadata, max_cll 1000 and max_fall 400.
+const METADATA: [u8; 8] = [0x2a, 0x06, 0x01, 0x03, 0xe8, 0x01, 0x90, 0x80];
+
+#[test]
+fn stream_starting_with_sequence_header() {
+    let bytes = [&SEQUENCE_HEADER[..], &FRAME, &SEQUENCE_HEADER, &FRAME].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let obus = parser
+        .iter(&mut buf)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(obus.len(), 4);
+    assert!(matches!(obus[0], Obu::SequenceHeader(_)));
+    assert!(matches!(&obus[1], Obu::Frame(it) if it.frame_type() == FrameType::KeyFrame));
+    assert!(matches!(obus[2], Obu::SequenceHeader(_)));
+    assert!(matches!(obus[3], Obu::Frame(_)));
+
+    // Repeating the same sequence header keeps the decoder state.
+    assert_eq!(parser.frames_parsed(), 2);
+}
+
+#[test]
+fn new_sequence_header_without_temporal_delimiter() {
+    let mut parser = ObuParser::default();
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    assert_eq!(
+        parser.context().ref_frame_type(0),
+        Some(FrameType::KeyFrame)
+    );
+
+    parser
+        .parse(&mut Buffer::new(&OTHER_SEQUENCE_HEADER))
+        .unwrap();
+
+    let ctx = parser.context();
+    assert_eq!(
+        ctx.sequence_header().unwrap().to_string(),
+        "Main 1920x1080 8bit 4:2:0 level 4.1"
+    );
+    assert_eq!(ctx.frames_parsed(), 0);
+    assert!(ctx.frame_header().is_none());
+    assert_eq!(ctx.ref_frame_type(0), None);
+
+    parser.parse(&mut Buffer::new(&FRAME)).unwrap();
+    assert_eq!(parser.frames_parsed(), 1);
+}
+
+/// Main profile 1280x720 10 bit 4:2:0 sequence header without order hints.
+const HD_10BIT_SEQUENCE_HEADER: [u8; 13] = [
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x42, 0xaa, 0x7f, 0xac, 0xf0, 0x06, 0xe0, 0x20,
+];
+
+/// A lossless single tile key frame for `HD_10BIT_SEQUENCE_HEADER`.
+const HD_10BIT_FRAME: [u8; 7] = [0x32, 0x05, 0x10, 0xc0, 0x00, 0x00, 0x00];
+
+#[test]
+fn switch_to_another_sequence_header() {
+    let bytes = [
+        &SEQUENCE_HEADER[..],
+        &FRAME,
+        &TEMPORAL_DELIMITER,
+        &HD_10BIT_SEQUENCE_HEADER,
+        &HD_10BIT_FRAME,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let obus = parser
+        .iter(&mut buf)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(obus.len(), 5);
+
+    let ctx = parser.context();
+    assert_eq!(
+        ctx.sequence_header().unwrap().to_string(),
+        "Main 1280x720 10bit 4:2:0 level 4.0"
+    );
+    assert_eq!(ctx.bit_depth(), 10);
+    assert_eq!(ctx.num_planes(), 3);
+    assert_eq!(ctx.frame_dimensions(), (1280, 720));
+    assert_eq!(ctx.frames_parsed(), 1);
+
+    // The frame is parsed without order_hint.
+    assert_eq!(ctx.order_hint(), 0);
+    let header = &obus[4].as_frame().unwrap().header.uncompressed_header;
+    assert_eq!(header.frame_type, FrameType::KeyFrame);
+    assert!(header.coded_lossless);
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[test]
+fn concatenated_streams() {
+    // The frame header of `FRAME`, the first stream is cut before its tile
+    // groups.
+    let frame_header = [0x1a, 0x05, 0x10, 0x00, 0xd0, 0x80, 0x01];
+    let first = [
+        &TEMPORAL_DELIMITER[..],
+        &SEQUENCE_HEADER,
+        &FRAME,
+        &TEMPORAL_DELIMITER,
+        &frame_header,
+    ]
+    .concat();
+    let second = [&TEMPORAL_DELIMITER[..], &OTHER_SEQUENCE_HEADER, &FRAME].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&first);
+    for obu in parser.iter(&mut buf) {
+        obu.unwrap();
+    }
+
+    assert_eq!(parser.frames_parsed(), 2);
+
+    // Nothing of the first stream is left once the second sequence header
+    // is parsed.
+    let mut buf = Buffer::new(&second);
+    parser.parse(&mut buf).unwrap();
+    parser.parse(&mut buf).unwrap();
+
+    let ctx = parser.context();
+    assert_eq!(ctx.frames_parsed(), 0);
+    assert!(ctx.frame_header().is_none());
+    assert!((0..8).all(|i| ctx.ref_frame_type(i).is_none()));
+
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(obu.as_frame().unwrap().frame_type(), FrameType::KeyFrame);
+    assert_eq!(parser.frames_parsed(), 1);
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[test]
+fn unsupported_tile_list() {
+    // A tile list, then padding.
+    let bytes = [
+        &[0x42, 0x04, 0x01, 0x02, 0x00, 0x00][..],
+        &[0x7a, 0x01, 0x00],
+        &TEMPORAL_DELIMITER,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(
+        parser.parse(&mut buf).unwrap_err(),
+        ObuError::Unsupported(ObuType::TileList)
+    );
+
+    // The tile list is skipped, unlike padding it is not dropped silently.
+    assert!(parser.parse(&mut buf).unwrap().is_drop());
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+}
+
+#[test]
+fn skip_obus() {
+    let bytes = [
+        &TEMPORAL_DELIMITER[..],
+        &SEQUENCE_HEADER,
+        &METADATA,
+        &METADATA,
+        &METADATA,
+        &FRAME,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    parser.skip(&mut buf, 5).unwrap();
+
+    // The sequence header was parsed on the way.
+    assert!(parser.sequence_header().is_some());
+    assert_eq!(buf.remaining_bytes(), FRAME.len());
+
+    let obu = parser.parse(&mut buf).unwrap();
+    assert_eq!(obu.as_frame().unwrap().frame_type(), FrameType::KeyFrame);
+
+    let mut buf = Buffer::new(&bytes);
+    assert!(matches!(
+        parser.skip(&mut buf, 7),
+        Err(ObuError::UnexpectedEof)
+    ));
+}
+
+#[test]
+fn empty_input() {
+    let mut parser = ObuParser::default();
+
+    let mut buf = Buffer::new(&[]);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+    assert!(parser.iter(&mut buf).next().is_none());
+
+    // The end of a stream behaves like an empty one.
+    let mut buf = Buffer::new(&TEMPORAL_DELIMITER);
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+    assert!(parser.iter(&mut buf).next().is_none());
+}
+
+#[test]
+fn detect_low_overhead_and_annexb_formats() {
+    let low_overhead = [&TEMPORAL_DELIMITER[..], &SEQUENCE_HEADER, &FRAME].concat();
+
+    // The same OBUs in a single frame unit, the temporal delimiter without
+    // obu_size.
+    let annexb = [
+        &[39, 38, 1, 0x10, SEQUENCE_HEADER.len() as u8][..],
+        &SEQUENCE_HEADER,
+        &[FRAME.len() as u8],
+        &FRAME,
+    ]
+    .concat();
+
+    let buf = Buffer::new(&low_overhead);
+    assert_eq!(ObuParser::detect_format(&buf), BitstreamFormat::LowOverhead);
+    assert_eq!(buf.get_position(), 0);
+
+    let mut buf = Buffer::new(&annexb);
+    assert_eq!(ObuParser::detect_format(&buf), BitstreamFormat::AnnexB);
+    assert_eq!(buf.get_position(), 0);
+
+    let obus = ObuParser::default()
+        .parse_annexb_temporal_unit(&mut buf)
+        .unwrap();
+    assert_eq!(obus.len(), 3);
+    assert!(matches!(obus[2], Obu::Frame(_)));
+
+    // A stream starting with a sequence header is detected as well.
+    let buf = Buffer::new(&SEQUENCE_HEADER);
+    assert_eq!(ObuParser::detect_format(&buf), BitstreamFormat::LowOverhead);
+
+    for bytes in [&[][..], &[0xff, 0xff], &[0x12, 0x05]] {
+        assert_eq!(
+            ObuParser::detect_format(&Buffer::new(bytes)),
+            BitstreamFormat::Unknown
+        );
+    }
+}
+
+#[test]
+fn truncated_obu_size() {
+    let mut parser = ObuParser::default();
+
+    // A temporal delimiter whose obu_size is cut after a continuation byte.
+    let mut buf = Buffer::new(&[0x12, 0x80]);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+
+    // obu_size longer than 8 bytes.
+    let mut buf = Buffer::new(&[0x12, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::InvalidLeb128);
+}
+
+#[test]
+fn obu_size_larger_than_the_buffer() {
+    // METADATA with an obu_size of 100.
+    let bytes = [&[0x2a, 100][..], &METADATA[2..]].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+
+    // The payload is parsed as far as it goes.
+    parser.set_mode(ParseMode::Lenient);
+    let mut buf = Buffer::new(&bytes);
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::Metadata(_))));
+    assert!(parser.is_truncated());
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[test]
+fn obu_size_mismatch() {
+    // METADATA with two bytes of padding counted in obu_size.
+    let bytes = [
+        &[0x2a, 0x08][..],
+        &METADATA[2..],
+        &[0x00, 0x00],
+        &TEMPORAL_DELIMITER,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::Metadata(_))));
+    assert!(parser.has_trailing_padding());
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+
+    // METADATA with an obu_size of 4, max_fall is read into the temporal
+    // delimiter.
+    let bytes = [&[0x2a, 0x04][..], &METADATA[2..6], &TEMPORAL_DELIMITER].concat();
+
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(
+        parser.parse(&mut buf).unwrap_err(),
+        ObuError::ObuSizeOverrun
+    );
+
+    // The parser skips to the end of the OBU and carries on.
+    parser.set_mode(ParseMode::Lenient);
+    let mut buf = Buffer::new(&bytes);
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::Metadata(_))));
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+    assert_eq!(buf.remaining_bytes(), 0);
+}
+
+#[test]
+fn streaming_obu_split_across_pushes() {
+    // SEQUENCE_HEADER with obu_size coded on two bytes.
+    let bytes = [
+        &[0x0a, 0x8b, 0x00][..],
+        &SEQUENCE_HEADER[2..],
+        &TEMPORAL_DELIMITER,
+    ]
+    .concat();
+
+    let mut parser = StreamingObuParser::default();
+    parser.push(&bytes[..2]);
+    assert!(parser.pull().is_none());
+
+    parser.push(&bytes[2..8]);
+    assert!(parser.pull().is_none());
+
+    parser.push(&bytes[8..]);
+    assert!(matches!(parser.pull(), Some(Ok(Obu::SequenceHeader(_)))));
+    assert!(matches!(parser.pull(), Some(Ok(Obu::TemporalDelimiter))));
+    assert!(parser.pull().is_none());
+}
+
+#[test]
+fn streaming_invalid_obu_size() {
+    let mut parser = StreamingObuParser::default();
+
+    // obu_size longer than 8 bytes.
+    parser.push(&[0x12, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]);
+    assert_eq!(parser.pull().unwrap().unwrap_err(), ObuError::InvalidLeb128);
+
+    // The pending bytes are dropped.
+    assert!(parser.pull().is_none());
+    parser.push(&TEMPORAL_DELIMITER);
+    assert!(matches!(parser.pull(), Some(Ok(Obu::TemporalDelimiter))));
+}
+
+#[test]
+fn resync_after_garbage() {
+    // A temporal delimiter with an extension header, temporal_id and
+    // spatial_id are 0.
+    let temporal_delimiter = [0x16, 0x00, 0x00];
+    let bytes = [
+        &TEMPORAL_DELIMITER[..],
+        &SEQUENCE_HEADER,
+        &FRAME,
+        &[0xff, 0xff, 0x7f, 0x33],
+        &temporal_delimiter,
+        &FRAME,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    for _ in 0..3 {
+        parser.parse(&mut buf).unwrap();
+    }
+
+    assert!(parser.parse(&mut buf).is_err());
+    assert!(parser.resync(&mut buf));
+    assert_eq!(
+        buf.remaining_bytes(),
+        temporal_delimiter.len() + FRAME.len()
+    );
+
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::TemporalDelimiter)));
+    assert!(matches!(parser.parse(&mut buf), Ok(Obu::Frame(_))));
+    assert_eq!(parser.frames_parsed(), 2);
+
+    assert!(!parser.resync(&mut buf));
+}
+
+/// Two Annex B temporal units, the OBUs don't carry obu_size. The first one
+/// holds a temporal delimiter, `SEQUENCE_HEADER` and `FRAME`, the second one
+/// a temporal delimiter and `FRAME`.
+fn annexb_temporal_units() -> Vec<u8> {
+    let sequence_header = [&[0x08][..], &SEQUENCE_HEADER[2..]].concat();
+    let frame = [&[0x30][..], &FRAME[2..]].concat();
+
+    [
+        &[37, 36, 1, 0x10, sequence_header.len() as u8][..],
+        &sequence_header,
+        &[frame.len() as u8],
+        &frame,
+        &[24, 23, 1, 0x10, frame.len() as u8],
+        &frame,
+    ]
+    .concat()
+}
+
+#[test]
+fn annexb_temporal_units_without_obu_size() {
+    let bytes = annexb_temporal_units();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+
+    let obus = parser.parse_annexb_temporal_unit(&mut buf).unwrap();
+    assert_eq!(obus.len(), 3);
+    assert!(obus[0].is_temporal_delimiter());
+    assert!(obus[1].as_sequence_header().is_some());
+    assert_eq!(
+        obus[2].as_frame().unwrap().frame_type(),
+        FrameType::KeyFrame
+    );
+
+    let obus = parser.parse_annexb_temporal_unit(&mut buf).unwrap();
+    assert_eq!(obus.len(), 2);
+    assert!(obus[1].as_frame().is_some());
+
+    assert_eq!(parser.frames_parsed(), 2);
+    assert_eq!(buf.remaining_bytes(), 0);
+    assert_eq!(
+        parser.parse_annexb_temporal_unit(&mut buf).unwrap_err(),
+        ObuError::UnexpectedEof
+    );
+}
+
+#[test]
+fn annexb_unit_overruns_its_parent() {
+    let mut parser = ObuParser::default();
+
+    // A frame unit of 3 bytes in a temporal unit of 2 bytes.
+    let mut buf = Buffer::new(&[0x02, 0x03, 0x01, 0x10, 0x00, 0x00]);
+    assert_eq!(
+        parser.parse_annexb_temporal_unit(&mut buf).unwrap_err(),
+        ObuError::InvalidObuLength
+    );
+
+    // An OBU of 5 bytes in a frame unit of 3 bytes.
+    let mut buf = Buffer::new(&[0x03, 0x05, 0x10, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(
+        parser.parse_annexb_frame_unit(&mut buf).unwrap_err(),
+        ObuError::InvalidObuLength
+    );
+
+    // A temporal unit of 5 bytes cut short by the end of the bitstream.
+    let mut buf = Buffer::new(&[0x05, 0x01, 0x10]);
+    assert_eq!(
+        parser.parse_annexb_temporal_unit(&mut buf).unwrap_err(),
+        ObuError::UnexpectedEof
+    );
+
+    // An obu_length cut short by the end of the bitstream.
+    let mut buf = Buffer::new(&[0x01, 0x80]);
+    assert_eq!(
+        parser.parse_annexb_frame_unit(&mut buf).unwrap_err(),
+        ObuError::UnexpectedEof
+    );
+}
+
+#[test]
+fn annexb_frame_unit_with_obus_without_obu_size() {
+    // METADATA without obu_size, then a temporal delimiter without obu_size.
+    let metadata = [&[0x28][..], &METADATA[2..]].concat();
+    let bytes = [
+        &[10, metadata.len() as u8][..],
+        &metadata,
+        &[1, 0x10],
+        &TEMPORAL_DELIMITER,
+    ]
+    .concat();
+
+    // obu_length ends the metadata, it would otherwise extend to the end of
+    // the buffer and its trailing bits would be wrong.
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let obus = parser.parse_annexb_frame_unit(&mut buf).unwrap();
+    assert_eq!(obus.len(), 2);
+    assert!(matches!(obus[0], Obu::Metadata(_)));
+    assert!(obus[1].is_temporal_delimiter());
+    assert_eq!(buf.remaining_bytes(), TEMPORAL_DELIMITER.len());
+
+    let mut buf = Buffer::new(&bytes[2..]);
+    let obu = parser.parse_with_length(&mut buf, metadata.len()).unwrap();
+    assert!(matches!(obu, Obu::Metadata(_)));
+    assert!(!parser.has_trailing_padding());
+    assert_eq!(buf.remaining_bytes(), 4);
+}
+
+#[cfg(feature = "checked-buffer")]
+#[test]
+fn truncated_obus_with_checked_reads() {
+    let mut parser = ObuParser::default();
+    parser.set_mode(ParseMode::Lenient);
+
+    // The payload is cut short, obu_size is clamped to the bytes left and the
+    // decoder reads past the end of the buffer.
+    let mut buf = Buffer::new(&SEQUENCE_HEADER[..8]);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+
+    parser.parse(&mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+    let mut buf = Buffer::new(&FRAME[..5]);
+    assert_eq!(parser.parse(&mut buf).unwrap_err(), ObuError::UnexpectedEof);
+}
+
+#[test]
+fn peek_obu_type_then_parse() {
+    let bytes = [&TEMPORAL_DELIMITER[..], &SEQUENCE_HEADER, &METADATA, &FRAME].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let mut types = Vec::new();
+    while buf.remaining_bytes() > 0 {
+        let position = buf.get_position();
+        let r#type = ObuParser::peek_obu_type(&buf).unwrap();
+        assert_eq!(buf.get_position(), position);
+
+        let obu = parser.parse(&mut buf).unwrap();
+        let parsed = match obu {
+            Obu::TemporalDelimiter => ObuType::TemporalDelimiter,
+            Obu::SequenceHeader(_) => ObuType::SequenceHeader,
+            Obu::Metadata(_) => ObuType::Metadata,
+            Obu::Frame(_) => ObuType::Frame,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(r#type, parsed);
+        types.push(r#type);
+    }
+
+    assert_eq!(
+        types,
+        [
+            ObuType::TemporalDelimiter,
+            ObuType::SequenceHeader,
+            ObuType::Metadata,
+            ObuType::Frame
+        ]
+    );
+}
+
+#[test]
+fn iterate_with_a_for_loop() {
+    let bytes = [&TEMPORAL_DELIMITER[..], &SEQUENCE_HEADER, &METADATA, &FRAME].concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let mut count = 0;
+    for obu in parser.iter(&mut buf) {
+        obu.unwrap();
+        count += 1;
+    }
+
+    assert_eq!(count, 4);
+    assert_eq!(buf.remaining_bytes(), 0);
+
+    // The error ends the iteration, the OBUs after it are not parsed.
+    let bytes = [&TEMPORAL_DELIMITER[..], &FRAME, &TEMPORAL_DELIMITER].concat();
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    let results = parser.iter(&mut buf).collect::<Vec<_>>();
+
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[0], Ok(Obu::TemporalDelimiter)));
+    assert!(matches!(results[1], Err(ObuError::NotFoundSequenceHeader)));
+}
+
+#[test]
+fn parse_stats() {
+    let padding = [0x7a, 0x01, 0xff];
+    let bytes = [
+        &TEMPORAL_DELIMITER[..],
+        &SEQUENCE_HEADER,
+        &METADATA,
+        &FRAME,
+        &padding,
+        &TEMPORAL_DELIMITER,
+        &FRAME,
+    ]
+    .concat();
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(parser.iter(&mut buf).filter(Result::is_ok).count(), 7);
+    assert!(parser.stats().is_none());
+
+    let mut parser = ObuParser::default();
+    parser.collect_stats(true);
+    let mut buf = Buffer::new(&bytes);
+    assert_eq!(parser.iter(&mut buf).filter(Result::is_ok).count(), 7);
+
+    let stats = parser.stats().unwrap();
+    let tally = |r#type| {
+        (
+            stats.obu_count.get(&r#type).copied().unwrap_or(0),
+            stats.obu_bytes.get(&r#type).copied().unwrap_or(0),
+        )
+    };
+
+    assert_eq!(tally(ObuType::TemporalDelimiter), (2, 4));
+    assert_eq!(tally(ObuType::SequenceHeader), (1, SEQUENCE_HEADER.len()));
+    assert_eq!(tally(ObuType::Metadata), (1, METADATA.len()));
+    assert_eq!(tally(ObuType::Frame), (2, 2 * FRAME.len()));
+    assert_eq!(tally(ObuType::Padding), (1, padding.len()));
+    assert_eq!(tally(ObuType::TileGroup), (0, 0));
+
+    assert_eq!(stats.frame_count.get(&FrameType::KeyFrame), Some(&2));
+    assert_eq!(stats.frame_count.len(), 1);
+    assert_eq!(stats.dropped, 1);
+
+    // Enabling it again keeps the totals, disabling it drops them.
+    parser.collect_stats(true);
+    assert_eq!(parser.stats().unwrap().dropped, 1);
+    parser.collect_stats(false);
+    assert!(parser.stats().is_none());
+}